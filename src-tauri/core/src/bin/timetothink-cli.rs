@@ -0,0 +1,120 @@
+//! Controls a running TimeToThink from the terminal:
+//! `timetothink-cli start|pause|reset|skip|status [--json] [--confirm PHRASE]`.
+
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[cfg(unix)]
+const USAGE: &str = "\
+Usage: timetothink-cli <start|pause|reset|skip|status> [options]
+
+Options:
+  --json             Print the response as JSON
+  --confirm PHRASE   Confirmation phrase for skipping an enforced break
+  --socket PATH      Socket of the running app (default: $TTT_SOCKET or the runtime dir)";
+
+#[cfg(unix)]
+fn main() -> ExitCode {
+    use timetothink_core::ipc::{self, Request, Response};
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+    let options = match Options::parse(args.into_iter()) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    let request = match options.command.as_str() {
+        "start" => Request::Start,
+        "pause" => Request::Pause,
+        "reset" => Request::Reset { confirmation: options.confirmation },
+        "skip" => Request::Skip { confirmation: options.confirmation },
+        "status" => Request::Status,
+        other => {
+            eprintln!("Unknown command: {}\n\n{}", other, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    let socket = options.socket.unwrap_or_else(ipc::default_socket_path);
+
+    let response = match ipc::send(&socket, &request) {
+        Ok(response) => response,
+        Err(message) => {
+            eprintln!("{}", message);
+            return ExitCode::from(2);
+        }
+    };
+    if options.json {
+        match serde_json::to_string_pretty(&response) {
+            Ok(json) => println!("{}", json),
+            Err(e) => eprintln!("Failed to format response: {}", e),
+        }
+    }
+    match response {
+        Response::Ok { update } => {
+            if !options.json {
+                let running = if update.is_running { "running" } else { "paused" };
+                println!(
+                    "{} {} ({}), {} completed",
+                    update.state.display_name(),
+                    update.countdown(),
+                    running,
+                    update.completed_pomodoros
+                );
+            }
+            ExitCode::SUCCESS
+        }
+        Response::Error { message, .. } => {
+            if !options.json {
+                eprintln!("{}", message);
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn main() -> ExitCode {
+    eprintln!("timetothink-cli needs Unix domain sockets, which this platform does not provide");
+    ExitCode::from(2)
+}
+
+#[cfg(unix)]
+struct Options {
+    command: String,
+    json: bool,
+    confirmation: Option<String>,
+    socket: Option<PathBuf>,
+}
+
+#[cfg(unix)]
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut command = None;
+        let mut json = false;
+        let mut confirmation = None;
+        let mut socket = None;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--json" => json = true,
+                "--confirm" => confirmation = Some(args.next().ok_or("--confirm needs a phrase")?),
+                "--socket" => socket = Some(PathBuf::from(args.next().ok_or("--socket needs a path")?)),
+                flag if flag.starts_with('-') => return Err(format!("Unknown option: {}", flag)),
+                _ if command.is_some() => return Err(format!("Unexpected argument: {}", arg)),
+                _ => command = Some(arg),
+            }
+        }
+        Ok(Self {
+            command: command.ok_or("Missing command")?,
+            json,
+            confirmation,
+            socket,
+        })
+    }
+}
@@ -0,0 +1,248 @@
+//! TypeScript bindings for the webviews: every type crossing the IPC boundary,
+//! a typed wrapper per command and a payload map for engine events. The output
+//! is checked in at `src/bindings.ts`; regenerate it with
+//! `npm run bindings` after changing any of them.
+
+use crate::events::EventName;
+use crate::history::SessionRecord;
+use crate::hooks::HookOutcome;
+use crate::settings::{Profile, Settings};
+use crate::shortcuts::ShortcutBinding;
+use crate::skip_policy::SkipStatus;
+use crate::stats::{DailyStats, StatsSummary, WeeklyStats};
+use crate::types::{BreakWarning, ExtensionOffer, PhaseChange, PhaseTransition, TimerUpdate};
+#[cfg(feature = "webhooks")]
+use crate::webhooks::DeliveryLogEntry;
+use chrono::{DateTime, Utc};
+use std::any::TypeId;
+use std::collections::{BTreeMap, HashSet};
+use ts_rs::{TypeVisitor, TS};
+
+pub const BINDINGS_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../src/bindings.ts");
+pub const UPDATE_BINDINGS_ENV: &str = "UPDATE_BINDINGS";
+
+const HEADER: &str = "\
+// Generated by src-tauri/core/src/bindings.rs. Do not edit by hand; run
+// `npm run bindings` to regenerate.
+import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+";
+
+const LISTEN_WRAPPER: &str = "\
+export type EventName = keyof EventPayloads;
+
+export function onEvent<E extends EventName>(
+  event: E,
+  handler: (payload: EventPayloads[E]) => void,
+): Promise<UnlistenFn> {
+  return listen<EventPayloads[E]>(event, (e) => handler(e.payload));
+}
+";
+
+/// Collects the declaration of every named type reachable from the types it visits.
+#[derive(Default)]
+struct Declarations {
+    seen: HashSet<TypeId>,
+    /// Keyed by name, since ts-rs does not visit dependencies in a stable order.
+    out: BTreeMap<String, String>,
+}
+
+impl TypeVisitor for Declarations {
+    fn visit<T: TS + 'static + ?Sized>(&mut self) {
+        // Only named types have an output path; primitives and wrappers are inlined
+        if T::output_path().is_some() {
+            if !self.seen.insert(TypeId::of::<T>()) {
+                return;
+            }
+            let declaration = format!("{}export {}", T::docs().unwrap_or_default(), T::decl());
+            self.out.insert(T::ident(), declaration);
+        }
+        T::visit_dependencies(self);
+        T::visit_generics(self);
+    }
+}
+
+impl Declarations {
+    /// The TypeScript name of `T`, declaring it and its dependencies on first use.
+    fn ty<T: TS + 'static>(&mut self) -> String {
+        self.visit::<T>();
+        T::name()
+    }
+
+    fn arg<T: TS + 'static>(&mut self, name: &'static str) -> Arg {
+        Arg { name, ty: self.ty::<T>(), optional: false }
+    }
+
+    /// An `Option` argument, which Tauri also accepts when the key is missing.
+    fn optional<T: TS + 'static>(&mut self, name: &'static str) -> Arg {
+        Arg { name, ty: format!("{} | null", self.ty::<T>()), optional: true }
+    }
+}
+
+struct Arg {
+    name: &'static str,
+    ty: String,
+    optional: bool,
+}
+
+struct Command {
+    name: &'static str,
+    args: Vec<Arg>,
+    returns: String,
+}
+
+fn command(name: &'static str, args: Vec<Arg>, returns: String) -> Command {
+    Command { name, args, returns }
+}
+
+/// The signature of every command in [`crate::app_commands!`], in the same
+/// order; a test compares the two lists.
+fn commands(d: &mut Declarations) -> Vec<Command> {
+    vec![
+        command("start_timer", vec![], d.ty::<()>()),
+        command("pause_timer", vec![], d.ty::<()>()),
+        command("reset_timer", vec![d.optional::<String>("confirmation")], d.ty::<()>()),
+        command("extend_phase", vec![d.optional::<u32>("minutes")], d.ty::<()>()),
+        command("reset_set", vec![], d.ty::<()>()),
+        command("skip_break", vec![d.optional::<String>("confirmation")], d.ty::<()>()),
+        command("get_skip_status", vec![], d.ty::<SkipStatus>()),
+        command("get_timer_state", vec![], d.ty::<TimerUpdate>()),
+        command(
+            "get_history",
+            vec![d.optional::<DateTime<Utc>>("from"), d.optional::<DateTime<Utc>>("to")],
+            d.ty::<Vec<SessionRecord>>(),
+        ),
+        command("get_daily_stats", vec![d.optional::<u32>("days")], d.ty::<Vec<DailyStats>>()),
+        command("get_weekly_stats", vec![d.optional::<u32>("weeks")], d.ty::<Vec<WeeklyStats>>()),
+        command("get_stats_summary", vec![], d.ty::<StatsSummary>()),
+        command("get_settings", vec![], d.ty::<Settings>()),
+        command("update_settings", vec![d.arg::<Settings>("settings")], d.ty::<Settings>()),
+        command("list_profiles", vec![], d.ty::<Vec<Profile>>()),
+        command("create_profile", vec![d.arg::<Profile>("profile")], d.ty::<Settings>()),
+        command(
+            "update_profile",
+            vec![d.arg::<String>("name"), d.arg::<Profile>("profile")],
+            d.ty::<Settings>(),
+        ),
+        command("delete_profile", vec![d.arg::<String>("name")], d.ty::<Settings>()),
+        command("switch_profile", vec![d.arg::<String>("name")], d.ty::<Settings>()),
+        command("test_hook", vec![d.arg::<PhaseChange>("change")], d.ty::<HookOutcome>()),
+        #[cfg(feature = "webhooks")]
+        command("get_webhook_deliveries", vec![], d.ty::<Vec<DeliveryLogEntry>>()),
+        command("list_shortcuts", vec![], d.ty::<Vec<ShortcutBinding>>()),
+    ]
+}
+
+fn payload(event: EventName, d: &mut Declarations) -> String {
+    match event {
+        EventName::TimerUpdate => d.ty::<TimerUpdate>(),
+        EventName::ShowGuard | EventName::HideGuard => d.ty::<()>(),
+        EventName::ExtensionOffer => d.ty::<ExtensionOffer>(),
+        EventName::BreakWarning => d.ty::<BreakWarning>(),
+        EventName::PhaseTransition => d.ty::<PhaseTransition>(),
+    }
+}
+
+/// Tauri matches command arguments by their camelCase names.
+fn camel_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    let mut upper = false;
+    for c in name.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            out.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn render_command(command: &Command) -> String {
+    let call = format!("invoke<{}>('{}'", command.returns, command.name);
+    if command.args.is_empty() {
+        return format!("  {}: () => {}),\n", camel_case(command.name), call);
+    }
+    let fields: Vec<String> = command
+        .args
+        .iter()
+        .map(|arg| {
+            let marker = if arg.optional { "?" } else { "" };
+            format!("{}{}: {}", camel_case(arg.name), marker, arg.ty)
+        })
+        .collect();
+    let default = if command.args.iter().all(|arg| arg.optional) { " = {}" } else { "" };
+    format!(
+        "  {}: (args: {{ {} }}{}) => {}, args),\n",
+        camel_case(command.name),
+        fields.join("; "),
+        default,
+        call
+    )
+}
+
+pub fn render() -> String {
+    let mut declarations = Declarations::default();
+    let commands = commands(&mut declarations);
+    let events: Vec<(EventName, String)> = EventName::ALL
+        .into_iter()
+        .map(|event| (event, payload(event, &mut declarations)))
+        .collect();
+
+    let mut out = String::from(HEADER);
+    for declaration in declarations.out.values() {
+        out.push('\n');
+        out.push_str(declaration);
+        out.push('\n');
+    }
+
+    out.push_str("\nexport const commands = {\n");
+    for command in &commands {
+        out.push_str(&render_command(command));
+    }
+    out.push_str("};\n\nexport interface EventPayloads {\n");
+    for (event, payload) in &events {
+        out.push_str(&format!("  {}: {};\n", event.as_str(), payload));
+    }
+    out.push_str("}\n\n");
+    out.push_str(LISTEN_WRAPPER);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::COMMAND_NAMES;
+
+    // The app always enables `webhooks`, so the checked-in file includes its commands.
+    #[test]
+    #[cfg_attr(not(feature = "webhooks"), ignore)]
+    fn bindings_are_up_to_date() {
+        let generated = render();
+        if std::env::var_os(UPDATE_BINDINGS_ENV).is_some() {
+            std::fs::write(BINDINGS_PATH, &generated).expect("failed to write bindings");
+            return;
+        }
+        let checked_in = std::fs::read_to_string(BINDINGS_PATH).unwrap_or_default();
+        assert!(
+            checked_in == generated,
+            "{} is stale; run `npm run bindings` to regenerate it",
+            BINDINGS_PATH
+        );
+    }
+
+    #[test]
+    #[cfg_attr(not(feature = "webhooks"), ignore)]
+    fn every_registered_command_has_a_binding() {
+        let bound: Vec<&str> = commands(&mut Declarations::default()).iter().map(|command| command.name).collect();
+        assert_eq!(bound, COMMAND_NAMES, "commands() no longer matches app_commands!");
+    }
+
+    #[test]
+    fn command_arguments_use_camel_case() {
+        assert_eq!(camel_case("get_daily_stats"), "getDailyStats");
+        assert_eq!(camel_case("days"), "days");
+    }
+}
@@ -0,0 +1,14 @@
+use std::time::SystemTime;
+
+/// Source of wall-clock time for the timer engine.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> SystemTime;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
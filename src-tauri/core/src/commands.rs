@@ -0,0 +1,44 @@
+//! The names of the Tauri commands the app registers, kept in one list that
+//! both the app's `generate_handler!` and the TypeScript bindings are built from.
+
+/// Invokes `$handler!` with every command name, in the order the bindings list
+/// them. The app registers its commands with
+/// `timetothink_core::app_commands!(tauri::generate_handler)`.
+#[macro_export]
+macro_rules! app_commands {
+    ($($handler:ident)::+) => {
+        $($handler)::+![
+            start_timer,
+            pause_timer,
+            reset_timer,
+            extend_phase,
+            reset_set,
+            skip_break,
+            get_skip_status,
+            get_timer_state,
+            get_history,
+            get_daily_stats,
+            get_weekly_stats,
+            get_stats_summary,
+            get_settings,
+            update_settings,
+            list_profiles,
+            create_profile,
+            update_profile,
+            delete_profile,
+            switch_profile,
+            test_hook,
+            get_webhook_deliveries,
+            list_shortcuts
+        ]
+    };
+}
+
+macro_rules! names {
+    ($($name:ident),*) => {
+        &[$(stringify!($name)),*]
+    };
+}
+
+/// The commands in [`app_commands!`], as Tauri names them.
+pub const COMMAND_NAMES: &[&str] = app_commands!(names);
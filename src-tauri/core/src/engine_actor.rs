@@ -0,0 +1,245 @@
+use crate::settings::Profile;
+use crate::skip_policy::{SkipDenied, SkipStatus};
+use crate::timer_engine::TimerEngine;
+use crate::types::{PhaseStatus, TimerUpdate};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot, watch};
+use tokio::time::{interval, MissedTickBehavior};
+
+/// The engine derives the countdown from its deadline, so ticks only need to be
+/// frequent enough to keep the display smooth; late ticks are simply dropped.
+const TICK_INTERVAL: Duration = Duration::from_millis(250);
+const COMMAND_QUEUE_SIZE: usize = 32;
+
+type Reply<T> = oneshot::Sender<T>;
+
+/// Requests handled by the engine task, each answered on its reply channel.
+/// Engine errors are turned into strings so replies can cross tasks.
+pub enum EngineCommand {
+    Start(Reply<Result<(), String>>),
+    Pause(Reply<Result<(), String>>),
+    Reset {
+        confirmation: Option<String>,
+        reply: Reply<Result<(), String>>,
+    },
+    SkipBreak {
+        confirmation: Option<String>,
+        reply: Reply<Result<(), SkipDenied>>,
+    },
+    Extend {
+        minutes: Option<u32>,
+        reply: Reply<Result<(), String>>,
+    },
+    ResetSet(Reply<Result<(), String>>),
+    ApplyProfile {
+        profile: Profile,
+        break_warnings: Vec<u32>,
+        reply: Reply<Result<(), String>>,
+    },
+    GetUpdate(Reply<TimerUpdate>),
+    GetSkipStatus(Reply<SkipStatus>),
+}
+
+/// Returned by [`EngineHandle`] once the engine task has shut down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EngineStopped;
+
+impl std::fmt::Display for EngineStopped {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "The timer engine has stopped")
+    }
+}
+
+impl std::error::Error for EngineStopped {}
+
+impl From<EngineStopped> for String {
+    fn from(stopped: EngineStopped) -> Self {
+        stopped.to_string()
+    }
+}
+
+/// Cheap, cloneable front for the engine task. Commands are processed one at
+/// a time in the order they are sent.
+#[derive(Clone)]
+pub struct EngineHandle {
+    commands: mpsc::Sender<EngineCommand>,
+    phase: watch::Receiver<PhaseStatus>,
+}
+
+/// Owns the engine and runs it until every [`EngineHandle`] is dropped.
+pub struct EngineActor {
+    engine: TimerEngine,
+    commands: mpsc::Receiver<EngineCommand>,
+}
+
+pub fn channel(engine: TimerEngine) -> (EngineHandle, EngineActor) {
+    let (tx, rx) = mpsc::channel(COMMAND_QUEUE_SIZE);
+    let handle = EngineHandle {
+        commands: tx,
+        phase: engine.subscribe_phase(),
+    };
+    (handle, EngineActor { engine, commands: rx })
+}
+
+impl EngineHandle {
+    async fn request<T>(&self, command: impl FnOnce(Reply<T>) -> EngineCommand) -> Result<T, EngineStopped> {
+        let (reply, response) = oneshot::channel();
+        self.commands.send(command(reply)).await.map_err(|_| EngineStopped)?;
+        response.await.map_err(|_| EngineStopped)
+    }
+
+    pub async fn start(&self) -> Result<Result<(), String>, EngineStopped> {
+        self.request(EngineCommand::Start).await
+    }
+
+    pub async fn pause(&self) -> Result<Result<(), String>, EngineStopped> {
+        self.request(EngineCommand::Pause).await
+    }
+
+    pub async fn reset(&self, confirmation: Option<String>) -> Result<Result<(), String>, EngineStopped> {
+        self.request(|reply| EngineCommand::Reset { confirmation, reply }).await
+    }
+
+    pub async fn skip_break(&self, confirmation: Option<String>) -> Result<Result<(), SkipDenied>, EngineStopped> {
+        self.request(|reply| EngineCommand::SkipBreak { confirmation, reply }).await
+    }
+
+    pub async fn extend_phase(&self, minutes: Option<u32>) -> Result<Result<(), String>, EngineStopped> {
+        self.request(|reply| EngineCommand::Extend { minutes, reply }).await
+    }
+
+    pub async fn reset_set(&self) -> Result<Result<(), String>, EngineStopped> {
+        self.request(EngineCommand::ResetSet).await
+    }
+
+    pub async fn apply_profile(
+        &self,
+        profile: Profile,
+        break_warnings: Vec<u32>,
+    ) -> Result<Result<(), String>, EngineStopped> {
+        self.request(|reply| EngineCommand::ApplyProfile { profile, break_warnings, reply }).await
+    }
+
+    pub async fn get_update(&self) -> Result<TimerUpdate, EngineStopped> {
+        self.request(EngineCommand::GetUpdate).await
+    }
+
+    pub async fn skip_status(&self) -> Result<SkipStatus, EngineStopped> {
+        self.request(EngineCommand::GetSkipStatus).await
+    }
+
+    /// Latest published phase; never waits on the engine task.
+    pub fn phase(&self) -> PhaseStatus {
+        *self.phase.borrow()
+    }
+
+    pub fn subscribe_phase(&self) -> watch::Receiver<PhaseStatus> {
+        self.phase.clone()
+    }
+}
+
+impl EngineActor {
+    pub async fn run(mut self) {
+        let mut ticker = interval(TICK_INTERVAL);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        loop {
+            tokio::select! {
+                // Commands first, so a burst of them is never reordered around a tick
+                biased;
+                command = self.commands.recv() => match command {
+                    Some(command) => self.handle(command),
+                    None => break,
+                },
+                _ = ticker.tick() => {
+                    if let Err(e) = self.engine.tick().await {
+                        eprintln!("Timer tick error: {}", e);
+                    }
+                }
+            }
+        }
+        println!("[EngineActor] All handles dropped, stopping");
+    }
+
+    fn handle(&mut self, command: EngineCommand) {
+        let engine = &mut self.engine;
+        // A dropped reply only means the caller stopped waiting.
+        match command {
+            EngineCommand::Start(reply) => {
+                let _ = reply.send(engine.start().map_err(|e| e.to_string()));
+            }
+            EngineCommand::Pause(reply) => {
+                let _ = reply.send(engine.pause().map_err(|e| e.to_string()));
+            }
+            EngineCommand::Reset { confirmation, reply } => {
+                let _ = reply.send(engine.reset(confirmation.as_deref()).map_err(|e| e.to_string()));
+            }
+            EngineCommand::SkipBreak { confirmation, reply } => {
+                let _ = reply.send(engine.skip_break(confirmation.as_deref()));
+            }
+            EngineCommand::Extend { minutes, reply } => {
+                let _ = reply.send(engine.extend_phase(minutes).map_err(|e| e.to_string()));
+            }
+            EngineCommand::ResetSet(reply) => {
+                let _ = reply.send(engine.reset_set().map_err(|e| e.to_string()));
+            }
+            EngineCommand::ApplyProfile { profile, break_warnings, reply } => {
+                engine.set_break_warnings(&break_warnings);
+                let _ = reply.send(engine.apply_profile(&profile).map_err(|e| e.to_string()));
+            }
+            EngineCommand::GetUpdate(reply) => {
+                let _ = reply.send(engine.get_update());
+            }
+            EngineCommand::GetSkipStatus(reply) => {
+                let _ = reply.send(engine.skip_status());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SystemClock;
+    use crate::events::NullSink;
+    use crate::types::TimerState;
+    use std::sync::Arc;
+
+    fn spawn_engine() -> (EngineHandle, tokio::task::JoinHandle<()>) {
+        let engine = TimerEngine::new(&Profile::default(), Arc::new(SystemClock), Arc::new(NullSink));
+        let (handle, actor) = channel(engine);
+        (handle, tokio::spawn(actor.run()))
+    }
+
+    #[tokio::test]
+    async fn commands_are_applied_in_order() {
+        let (handle, _task) = spawn_engine();
+        handle.start().await.unwrap().unwrap();
+        assert!(handle.get_update().await.unwrap().is_running);
+        assert!(handle.phase().is_running);
+
+        handle.pause().await.unwrap().unwrap();
+        let update = handle.get_update().await.unwrap();
+        assert!(!update.is_running);
+        assert_eq!(update.state, TimerState::Focus);
+        assert!(!handle.phase().is_running);
+    }
+
+    #[tokio::test]
+    async fn engine_errors_are_returned_to_the_caller() {
+        let (handle, _task) = spawn_engine();
+        assert_eq!(handle.skip_break(None).await.unwrap(), Err(SkipDenied::NotInBreak));
+        handle.start().await.unwrap().unwrap();
+        handle.extend_phase(Some(1)).await.unwrap().unwrap();
+        assert!(handle.extend_phase(Some(60)).await.unwrap().is_err());
+    }
+
+    #[tokio::test]
+    async fn actor_stops_when_all_handles_are_dropped() {
+        let (handle, task) = spawn_engine();
+        let second = handle.clone();
+        drop(handle);
+        second.get_update().await.unwrap();
+        drop(second);
+        task.await.unwrap();
+    }
+}
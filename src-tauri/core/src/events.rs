@@ -0,0 +1,129 @@
+use crate::types::{BreakWarning, ExtensionOffer, PhaseTransition, TimerUpdate};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EngineEvent {
+    TimerUpdate(TimerUpdate),
+    ShowGuard,
+    HideGuard,
+    ExtensionOffer(ExtensionOffer),
+    BreakWarning(BreakWarning),
+    PhaseTransition(PhaseTransition),
+}
+
+impl EngineEvent {
+    pub fn kind(&self) -> EventName {
+        match self {
+            EngineEvent::TimerUpdate(_) => EventName::TimerUpdate,
+            EngineEvent::ShowGuard => EventName::ShowGuard,
+            EngineEvent::HideGuard => EventName::HideGuard,
+            EngineEvent::ExtensionOffer(_) => EventName::ExtensionOffer,
+            EngineEvent::BreakWarning(_) => EventName::BreakWarning,
+            EngineEvent::PhaseTransition(_) => EventName::PhaseTransition,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.kind().as_str()
+    }
+}
+
+/// Names under which engine events reach the webviews; the generated
+/// TypeScript bindings map each one to its payload type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventName {
+    TimerUpdate,
+    ShowGuard,
+    HideGuard,
+    ExtensionOffer,
+    BreakWarning,
+    PhaseTransition,
+}
+
+impl EventName {
+    pub const ALL: [EventName; 6] = [
+        EventName::TimerUpdate,
+        EventName::ShowGuard,
+        EventName::HideGuard,
+        EventName::ExtensionOffer,
+        EventName::BreakWarning,
+        EventName::PhaseTransition,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EventName::TimerUpdate => "timer_update",
+            EventName::ShowGuard => "show_guard",
+            EventName::HideGuard => "hide_guard",
+            EventName::ExtensionOffer => "extension_offer",
+            EventName::BreakWarning => "break_warning",
+            EventName::PhaseTransition => "phase_transition",
+        }
+    }
+}
+
+/// Receives everything the timer engine publishes.
+pub trait EventSink: Send + Sync {
+    fn emit(&self, event: EngineEvent) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+/// Hands every event to each of several sinks. A failing sink does not keep the
+/// event from the others; the first error is returned once all have been tried.
+pub struct FanOutSink {
+    sinks: Vec<Arc<dyn EventSink>>,
+}
+
+impl FanOutSink {
+    pub fn new(sinks: Vec<Arc<dyn EventSink>>) -> Self {
+        Self { sinks }
+    }
+}
+
+impl EventSink for FanOutSink {
+    fn emit(&self, event: EngineEvent) -> Result<(), Box<dyn std::error::Error>> {
+        let mut first_error = None;
+        for sink in &self.sinks {
+            if let Err(e) = sink.emit(event.clone()) {
+                first_error.get_or_insert(e);
+            }
+        }
+        first_error.map_or(Ok(()), Err)
+    }
+}
+
+/// Republishes events to any number of async subscribers. A subscriber that
+/// falls behind misses events instead of holding up the engine.
+pub struct BroadcastSink {
+    events: broadcast::Sender<EngineEvent>,
+}
+
+impl BroadcastSink {
+    pub fn new(capacity: usize) -> Self {
+        let (events, _) = broadcast::channel(capacity);
+        Self { events }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<EngineEvent> {
+        self.events.subscribe()
+    }
+}
+
+impl EventSink for BroadcastSink {
+    fn emit(&self, event: EngineEvent) -> Result<(), Box<dyn std::error::Error>> {
+        // Having no subscribers right now is not an error.
+        let _ = self.events.send(event);
+        Ok(())
+    }
+}
+
+/// Discards every event, for tests that only drive the engine.
+#[cfg(test)]
+pub struct NullSink;
+
+#[cfg(test)]
+impl EventSink for NullSink {
+    fn emit(&self, _event: EngineEvent) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+}
@@ -0,0 +1,186 @@
+use crate::types::TimerState;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use ts_rs::TS;
+
+pub const HISTORY_FILE_NAME: &str = "history.jsonl";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionOutcome {
+    Completed,
+    Skipped,
+    Reset,
+}
+
+/// Extra time granted to a running focus phase.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+pub struct PhaseExtension {
+    pub at: DateTime<Utc>,
+    #[ts(type = "number")]
+    pub seconds: u64,
+}
+
+/// One finished or aborted phase.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+pub struct SessionRecord {
+    pub phase: TimerState,
+    pub outcome: SessionOutcome,
+    /// Length the phase was scheduled with, before any extensions.
+    #[ts(type = "number")]
+    pub planned_seconds: u64,
+    /// Time the phase actually spent running, excluding pauses.
+    #[ts(type = "number")]
+    pub actual_seconds: u64,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extensions: Vec<PhaseExtension>,
+}
+
+/// Destination for session records.
+pub trait HistoryRecorder: Send + Sync {
+    fn record(&self, record: &SessionRecord) -> Result<(), String>;
+}
+
+/// Append-only JSON Lines log of session records.
+pub struct HistoryLog {
+    path: PathBuf,
+}
+
+impl HistoryLog {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Returns the records that started within `[from, to)`, oldest first.
+    /// Either bound may be omitted.
+    pub fn query(
+        &self,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Result<Vec<SessionRecord>, String> {
+        let file = match std::fs::File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.to_string()),
+        };
+
+        let mut records = Vec::new();
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.map_err(|e| e.to_string())?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: SessionRecord = match serde_json::from_str(&line) {
+                Ok(record) => record,
+                Err(e) => {
+                    // A torn final line from a crash should not hide the rest of the log.
+                    eprintln!("Skipping history line {}: {}", index + 1, e);
+                    continue;
+                }
+            };
+            let after_from = from.is_none_or(|from| record.started_at >= from);
+            let before_to = to.is_none_or(|to| record.started_at < to);
+            if after_from && before_to {
+                records.push(record);
+            }
+        }
+        Ok(records)
+    }
+}
+
+impl HistoryRecorder for HistoryLog {
+    fn record(&self, record: &SessionRecord) -> Result<(), String> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let mut line = serde_json::to_string(record).map_err(|e| e.to_string())?;
+        line.push('\n');
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| e.to_string())?;
+        file.write_all(line.as_bytes()).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn at(raw: &str) -> DateTime<Utc> {
+        raw.parse().unwrap()
+    }
+
+    fn focus(started_at: &str) -> SessionRecord {
+        let started_at = at(started_at);
+        SessionRecord {
+            phase: TimerState::Focus,
+            outcome: SessionOutcome::Completed,
+            planned_seconds: 25 * 60,
+            actual_seconds: 25 * 60,
+            started_at,
+            ended_at: started_at + Duration::minutes(25),
+            extensions: Vec::new(),
+        }
+    }
+
+    fn log_with(dir: &tempfile::TempDir, records: &[SessionRecord]) -> HistoryLog {
+        let log = HistoryLog::new(dir.path().join(HISTORY_FILE_NAME));
+        for record in records {
+            log.record(record).unwrap();
+        }
+        log
+    }
+
+    #[test]
+    fn range_includes_from_and_excludes_to() {
+        let dir = tempfile::tempdir().unwrap();
+        let records = [focus("2024-05-01T09:00:00Z"), focus("2024-05-01T10:00:00Z"), focus("2024-05-01T11:00:00Z")];
+        let log = log_with(&dir, &records);
+
+        let found = log.query(Some(at("2024-05-01T10:00:00Z")), Some(at("2024-05-01T11:00:00Z"))).unwrap();
+        assert_eq!(found, records[1..2]);
+        let found = log.query(Some(at("2024-05-01T10:00:00Z")), None).unwrap();
+        assert_eq!(found, records[1..]);
+        let found = log.query(None, Some(at("2024-05-01T10:00:00Z"))).unwrap();
+        assert_eq!(found, records[..1]);
+    }
+
+    #[test]
+    fn open_range_returns_everything_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let records = [focus("2024-05-01T09:00:00Z"), focus("2024-05-02T09:00:00Z")];
+        let log = log_with(&dir, &records);
+        assert_eq!(log.query(None, None).unwrap(), records);
+    }
+
+    #[test]
+    fn unreadable_lines_are_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let records = [focus("2024-05-01T09:00:00Z"), focus("2024-05-01T10:00:00Z")];
+        let log = log_with(&dir, &records[..1]);
+        let mut file = OpenOptions::new().append(true).open(&log.path).unwrap();
+        file.write_all(b"not json\n\n").unwrap();
+        log.record(&records[1]).unwrap();
+        // A crash mid-write leaves a torn final line
+        let torn = serde_json::to_string(&records[1]).unwrap();
+        file.write_all(&torn.as_bytes()[..torn.len() / 2]).unwrap();
+
+        assert_eq!(log.query(None, None).unwrap(), records);
+    }
+
+    #[test]
+    fn missing_file_is_an_empty_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = HistoryLog::new(dir.path().join("missing").join(HISTORY_FILE_NAME));
+        assert_eq!(log.query(None, None).unwrap(), Vec::new());
+    }
+}
@@ -0,0 +1,304 @@
+//! Runs the user's hook commands when the timer changes phase. Each hook gets
+//! the `TimerUpdate` as JSON on stdin and a summary in `TTT_*` environment
+//! variables, runs in its own task so the timer never waits for it, and is
+//! killed once `hooks.timeout_seconds` have passed.
+//!
+//! Hooks are spawned with `tokio::process` rather than `tauri-plugin-shell`:
+//! the plugin's child handle can only close stdin by killing the process, and
+//! hooks that read their input to the end would then never finish.
+
+use crate::events::EngineEvent;
+use crate::settings::SharedSettings;
+use crate::types::{serde_name, PhaseChange, PhaseTransition, TimerUpdate};
+use serde::{Deserialize, Serialize};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::process::Command;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use ts_rs::TS;
+
+/// How long to keep reading the output of a killed hook.
+const OUTPUT_DRAIN_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// How much of each of a hook's output streams is kept; the rest is read and
+/// thrown away, so a chatty hook neither blocks on a full pipe nor fills memory.
+pub const MAX_CAPTURED_OUTPUT: usize = 64 * 1024;
+
+/// What happened when a hook ran.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+pub struct HookOutcome {
+    pub change: PhaseChange,
+    /// `None` when the hook was killed by a signal or timed out.
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub stdout: String,
+    pub stderr: String,
+    /// Whether `stdout` or `stderr` was cut at `MAX_CAPTURED_OUTPUT` bytes.
+    pub output_truncated: bool,
+    #[ts(type = "number")]
+    pub duration_ms: u64,
+}
+
+impl HookOutcome {
+    pub fn succeeded(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+/// Environment variables describing `transition`; `TTT_DRY_RUN` is `1` when
+/// the hook is being tried out rather than run for a real transition.
+pub fn hook_env(transition: &PhaseTransition, dry_run: bool) -> Vec<(&'static str, String)> {
+    let update = &transition.update;
+    vec![
+        ("TTT_EVENT", serde_name(&transition.change)),
+        ("TTT_FROM", serde_name(&transition.from)),
+        ("TTT_TO", serde_name(&transition.to)),
+        ("TTT_AT", transition.at.to_rfc3339()),
+        ("TTT_REMAINING_SECONDS", update.remaining_seconds.to_string()),
+        ("TTT_TOTAL_SECONDS", update.total_seconds.to_string()),
+        ("TTT_COMPLETED_POMODOROS", update.completed_pomodoros.to_string()),
+        ("TTT_IS_RUNNING", u8::from(update.is_running).to_string()),
+        ("TTT_DRY_RUN", u8::from(dry_run).to_string()),
+    ]
+}
+
+/// Runs `command` (program, then arguments) for `transition` and waits for it,
+/// at most `timeout`. Fails only if the command could not be started.
+pub async fn run_hook(
+    command: &[String],
+    transition: &PhaseTransition,
+    timeout: Duration,
+    dry_run: bool,
+) -> Result<HookOutcome, String> {
+    let (program, args) = command.split_first().ok_or("no hook command configured")?;
+    let input = serde_json::to_vec(&transition.update).map_err(|e| e.to_string())?;
+    let started = Instant::now();
+    let mut child = Command::new(program)
+        .args(args)
+        .envs(hook_env(transition, dry_run))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("failed to start {:?}: {}", program, e))?;
+    let stdin = child.stdin.take();
+    let (stdout, mut stdout_reader) = capture(child.stdout.take());
+    let (stderr, mut stderr_reader) = capture(child.stderr.take());
+
+    let run = async {
+        if let Some(mut stdin) = stdin {
+            // A hook that ignores its input may exit before reading it; that is fine.
+            let _ = stdin.write_all(&input).await;
+        }
+        let status = child.wait().await?;
+        let _ = (&mut stdout_reader).await;
+        let _ = (&mut stderr_reader).await;
+        Ok::<_, std::io::Error>(status)
+    };
+    let result = tokio::time::timeout(timeout, run).await;
+    let (exit_code, timed_out) = match result {
+        Ok(Ok(status)) => (status.code(), false),
+        Ok(Err(e)) => return Err(format!("failed to wait for {:?}: {}", program, e)),
+        Err(_) => {
+            if let Err(e) = child.kill().await {
+                eprintln!("[Hooks] Failed to kill {:?}: {}", program, e);
+            }
+            let drained = async {
+                let _ = (&mut stdout_reader).await;
+                let _ = (&mut stderr_reader).await;
+            };
+            let _ = tokio::time::timeout(OUTPUT_DRAIN_TIMEOUT, drained).await;
+            (None, true)
+        }
+    };
+    // Whatever the hook wrote before it was killed is kept; processes it
+    // started may still hold the pipes open, so stop reading them here.
+    stdout_reader.abort();
+    stderr_reader.abort();
+    let (stdout, stdout_truncated) = captured(&stdout);
+    let (stderr, stderr_truncated) = captured(&stderr);
+    Ok(HookOutcome {
+        change: transition.change,
+        exit_code,
+        timed_out,
+        stdout,
+        stderr,
+        output_truncated: stdout_truncated || stderr_truncated,
+        duration_ms: u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX),
+    })
+}
+
+#[derive(Default)]
+struct Output {
+    bytes: Vec<u8>,
+    truncated: bool,
+}
+
+type Captured = Arc<Mutex<Output>>;
+
+/// Reads `pipe` to the end in the background. The buffer fills as output
+/// arrives, so it holds everything written so far even if reading is cut short,
+/// up to `MAX_CAPTURED_OUTPUT` bytes.
+fn capture(pipe: Option<impl AsyncRead + Unpin + Send + 'static>) -> (Captured, JoinHandle<()>) {
+    let buffer = Captured::default();
+    let sink = buffer.clone();
+    let reader = tokio::spawn(async move {
+        let Some(mut pipe) = pipe else {
+            return;
+        };
+        let mut chunk = [0u8; 4096];
+        while let Ok(read @ 1..) = pipe.read(&mut chunk).await {
+            if let Ok(mut sink) = sink.lock() {
+                let kept = read.min(MAX_CAPTURED_OUTPUT - sink.bytes.len());
+                sink.bytes.extend_from_slice(&chunk[..kept]);
+                sink.truncated |= kept < read;
+            }
+        }
+    });
+    (buffer, reader)
+}
+
+fn captured(buffer: &Captured) -> (String, bool) {
+    buffer
+        .lock()
+        .map(|output| (String::from_utf8_lossy(&output.bytes).into_owned(), output.truncated))
+        .unwrap_or_default()
+}
+
+/// Runs the hook configured for `change` as if that change had just led to
+/// `update`, with `TTT_DRY_RUN=1`, so it can be tried out from the settings.
+pub async fn dry_run(
+    settings: &SharedSettings,
+    change: PhaseChange,
+    update: TimerUpdate,
+) -> Result<HookOutcome, String> {
+    let (command, timeout) = {
+        let store_guard = settings.lock().await;
+        let hooks = &store_guard.get().hooks;
+        (hooks.command(change).to_vec(), hooks.timeout_seconds)
+    };
+    let transition = PhaseTransition {
+        change,
+        from: update.state,
+        to: update.state,
+        at: chrono::Utc::now(),
+        update,
+    };
+    run_hook(&command, &transition, Duration::from_secs(u64::from(timeout)), true).await
+}
+
+/// Runs the configured hook for every phase transition until the event
+/// channel closes. Settings are read per transition, so edits apply at once.
+pub async fn run_hooks(settings: SharedSettings, mut events: broadcast::Receiver<EngineEvent>) {
+    loop {
+        let transition = match events.recv().await {
+            Ok(EngineEvent::PhaseTransition(transition)) => transition,
+            Ok(_) => continue,
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                eprintln!("[Hooks] Missed {} events", missed);
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+        let (command, timeout) = {
+            let store_guard = settings.lock().await;
+            let hooks = &store_guard.get().hooks;
+            (hooks.command(transition.change).to_vec(), hooks.timeout_seconds)
+        };
+        if command.is_empty() {
+            continue;
+        }
+        tokio::spawn(async move {
+            let timeout = Duration::from_secs(u64::from(timeout));
+            match run_hook(&command, &transition, timeout, false).await {
+                Ok(outcome) => log_outcome(&outcome),
+                Err(e) => eprintln!("[Hooks] {}: {}", serde_name(&transition.change), e),
+            }
+        });
+    }
+}
+
+fn log_outcome(outcome: &HookOutcome) {
+    let name = serde_name(&outcome.change);
+    for line in outcome.stderr.lines() {
+        eprintln!("[Hooks] {}: {}", name, line);
+    }
+    if outcome.output_truncated {
+        eprintln!("[Hooks] {} wrote more than {} bytes; the rest was dropped", name, MAX_CAPTURED_OUTPUT);
+    }
+    if outcome.timed_out {
+        eprintln!("[Hooks] {} timed out after {}ms and was killed", name, outcome.duration_ms);
+    } else if !outcome.succeeded() {
+        eprintln!("[Hooks] {} failed with exit code {:?}", name, outcome.exit_code);
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn transition() -> PhaseTransition {
+        PhaseTransition::sample(PhaseChange::BreakStarted)
+    }
+
+    fn sh(script: &str) -> Vec<String> {
+        vec!["sh".to_string(), "-c".to_string(), script.to_string()]
+    }
+
+    #[tokio::test]
+    async fn hooks_receive_the_update_on_stdin_and_env() {
+        let script = r#"read -r update; echo "$TTT_EVENT $TTT_TO $TTT_DRY_RUN"; echo "$update" >&2"#;
+        let outcome = run_hook(&sh(script), &transition(), Duration::from_secs(5), true).await.unwrap();
+
+        assert!(outcome.succeeded());
+        assert_eq!(outcome.stdout.trim(), "break_started ShortBreak 1");
+        let update: serde_json::Value = serde_json::from_str(outcome.stderr.trim()).unwrap();
+        assert_eq!(update["remaining_seconds"], transition().update.remaining_seconds);
+    }
+
+    #[tokio::test]
+    async fn slow_hooks_are_killed() {
+        let outcome = run_hook(&sh("sleep 30"), &transition(), Duration::from_millis(100), false)
+            .await
+            .unwrap();
+        assert!(outcome.timed_out);
+        assert_eq!(outcome.exit_code, None);
+        assert!(outcome.duration_ms < 5_000);
+    }
+
+    #[tokio::test]
+    async fn output_of_killed_hooks_is_kept() {
+        let script = "echo working; echo stuck >&2; sleep 30";
+        let outcome = run_hook(&sh(script), &transition(), Duration::from_millis(500), false)
+            .await
+            .unwrap();
+        assert!(outcome.timed_out);
+        assert_eq!(outcome.stdout.trim(), "working");
+        assert_eq!(outcome.stderr.trim(), "stuck");
+        assert!(outcome.duration_ms < 5_000);
+    }
+
+    #[tokio::test]
+    async fn long_output_is_cut() {
+        let script = "head -c 200000 /dev/zero | tr '\\0' x; echo done >&2";
+        let outcome = run_hook(&sh(script), &transition(), Duration::from_secs(5), false).await.unwrap();
+        assert!(outcome.succeeded());
+        assert!(outcome.output_truncated);
+        assert_eq!(outcome.stdout.len(), MAX_CAPTURED_OUTPUT);
+        assert_eq!(outcome.stderr.trim(), "done");
+
+        let outcome = run_hook(&sh("echo short"), &transition(), Duration::from_secs(5), false).await.unwrap();
+        assert!(!outcome.output_truncated);
+    }
+
+    #[tokio::test]
+    async fn missing_programs_are_reported() {
+        let command = vec!["/nonexistent/ttt-hook".to_string()];
+        assert!(run_hook(&command, &transition(), Duration::from_secs(1), false).await.is_err());
+        assert!(run_hook(&[], &transition(), Duration::from_secs(1), false).await.is_err());
+    }
+}
@@ -0,0 +1,478 @@
+//! Opt-in HTTP/JSON API on 127.0.0.1 for dashboards and scripts. Every route
+//! requires the bearer token stored next to the settings; the routes mirror the
+//! app's commands and are described by the OpenAPI document served at
+//! `/v1/openapi.json`.
+
+use crate::engine_actor::{EngineHandle, EngineStopped};
+use crate::events::{BroadcastSink, EngineEvent};
+use crate::history::{HistoryLog, SessionRecord};
+use crate::service;
+use crate::settings::{Profile, Settings, SharedSettings};
+use crate::skip_policy::{SkipDenied, SkipStatus};
+use crate::stats::{DailyStats, StatsSummary, WeeklyStats};
+use crate::types::TimerUpdate;
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{header, HeaderMap, Method, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post, put};
+use axum::{Json, Router};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::convert::Infallible;
+use std::io::Write;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{Stream, StreamExt};
+
+pub const TOKEN_FILE_NAME: &str = "http_api_token";
+const TOKEN_BYTES: usize = 32;
+const OPENAPI: &str = include_str!("openapi.json");
+const EVENTS_PATH: &str = "/v1/events";
+
+/// Everything the routes operate on.
+#[derive(Clone)]
+pub struct ApiState {
+    pub engine: EngineHandle,
+    pub settings: SharedSettings,
+    pub history: Arc<HistoryLog>,
+    pub events: Arc<BroadcastSink>,
+    pub token: Arc<str>,
+}
+
+/// Reads the API token from `path`, generating and saving a new one (readable
+/// only by the owner) the first time. Delete the file to rotate the token.
+pub fn load_or_create_token(path: &std::path::Path) -> Result<String, String> {
+    match std::fs::read_to_string(path) {
+        Ok(token) if !token.trim().is_empty() => return Ok(token.trim().to_string()),
+        // An empty file is replaced, as if it were missing
+        Ok(_) => std::fs::remove_file(path).map_err(|e| format!("failed to remove {}: {}", path.display(), e))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(format!("failed to read {}: {}", path.display(), e)),
+    }
+
+    let mut bytes = [0u8; TOKEN_BYTES];
+    getrandom::fill(&mut bytes).map_err(|e| format!("failed to generate API token: {}", e))?;
+    let token: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    // Created with its final mode, so the token is never readable by anyone else
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let write_error = |e: std::io::Error| format!("failed to write {}: {}", path.display(), e);
+    let mut file = options.open(path).map_err(write_error)?;
+    file.write_all(token.as_bytes()).map_err(write_error)?;
+    Ok(token)
+}
+
+/// Serves the API on 127.0.0.1:`port` until the process exits.
+pub async fn serve(port: u16, state: ApiState) -> std::io::Result<()> {
+    let listener = tokio::net::TcpListener::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, port))).await?;
+    println!("[HttpApi] Listening on http://{}", listener.local_addr()?);
+    axum::serve(listener, router(state)).await
+}
+
+pub fn router(state: ApiState) -> Router {
+    Router::new()
+        .route("/v1/openapi.json", get(openapi))
+        .route("/v1/timer", get(timer_state))
+        .route("/v1/timer/start", post(start_timer))
+        .route("/v1/timer/pause", post(pause_timer))
+        .route("/v1/timer/reset", post(reset_timer))
+        .route("/v1/timer/extend", post(extend_phase))
+        .route("/v1/timer/reset-set", post(reset_set))
+        .route("/v1/timer/skip", post(skip_break))
+        .route("/v1/timer/skip-status", get(skip_status))
+        .route("/v1/history", get(history))
+        .route("/v1/stats/daily", get(daily_stats))
+        .route("/v1/stats/weekly", get(weekly_stats))
+        .route("/v1/stats/summary", get(stats_summary))
+        .route("/v1/settings", get(get_settings).put(update_settings))
+        .route("/v1/profiles", get(list_profiles).post(create_profile))
+        .route("/v1/profiles/{name}", put(update_profile).delete(delete_profile))
+        .route("/v1/profiles/{name}/activate", post(switch_profile))
+        .route(EVENTS_PATH, get(events))
+        .layer(middleware::from_fn_with_state(state.clone(), require_token))
+        .with_state(state)
+}
+
+/// A failed request, answered as `{"error": ..., "denied": ...}`.
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    message: String,
+    denied: Option<SkipDenied>,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self { status, message: message.into(), denied: None }
+    }
+
+    fn invalid(message: String) -> Self {
+        Self::new(StatusCode::UNPROCESSABLE_ENTITY, message)
+    }
+}
+
+/// Engine commands fail when the timer's state does not allow them.
+impl From<String> for ApiError {
+    fn from(message: String) -> Self {
+        Self::new(StatusCode::CONFLICT, message)
+    }
+}
+
+impl From<SkipDenied> for ApiError {
+    fn from(denied: SkipDenied) -> Self {
+        Self { status: StatusCode::CONFLICT, message: denied.to_string(), denied: Some(denied) }
+    }
+}
+
+impl From<EngineStopped> for ApiError {
+    fn from(stopped: EngineStopped) -> Self {
+        Self::new(StatusCode::SERVICE_UNAVAILABLE, stopped.to_string())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = serde_json::json!({ "error": self.message, "denied": self.denied });
+        (self.status, Json(body)).into_response()
+    }
+}
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+async fn require_token(State(state): State<ApiState>, request: Request, next: Next) -> Response {
+    // `EventSource` cannot send headers, so the event stream also takes the token as a
+    // query parameter. Everywhere else it would end up in logs and shell history.
+    let query = (request.method() == Method::GET && request.uri().path() == EVENTS_PATH)
+        .then(|| query_token(request.uri().query()))
+        .flatten();
+    let presented = bearer_token(request.headers()).or(query);
+    match presented {
+        Some(token) if constant_time_eq(token.as_bytes(), state.token.as_bytes()) => next.run(request).await,
+        _ => ApiError::new(StatusCode::UNAUTHORIZED, "missing or invalid bearer token").into_response(),
+    }
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<String> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    value.strip_prefix("Bearer ").map(|token| token.trim().to_string())
+}
+
+fn query_token(query: Option<&str>) -> Option<String> {
+    query?
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("token="))
+        .map(str::to_string)
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+async fn openapi() -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "application/json")], OPENAPI)
+}
+
+async fn timer_state(State(state): State<ApiState>) -> ApiResult<TimerUpdate> {
+    Ok(Json(state.engine.get_update().await?))
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ConfirmationBody {
+    confirmation: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ExtendBody {
+    minutes: Option<u32>,
+}
+
+/// Timer commands answer with the state they left behind. Their bodies are
+/// optional, so an empty POST works as well.
+fn optional_body<T: Default>(body: Option<Json<T>>) -> T {
+    body.map(|Json(body)| body).unwrap_or_default()
+}
+
+async fn start_timer(State(state): State<ApiState>) -> ApiResult<TimerUpdate> {
+    state.engine.start().await??;
+    timer_state(State(state)).await
+}
+
+async fn pause_timer(State(state): State<ApiState>) -> ApiResult<TimerUpdate> {
+    state.engine.pause().await??;
+    timer_state(State(state)).await
+}
+
+async fn reset_timer(State(state): State<ApiState>, body: Option<Json<ConfirmationBody>>) -> ApiResult<TimerUpdate> {
+    state.engine.reset(optional_body(body).confirmation).await??;
+    timer_state(State(state)).await
+}
+
+async fn extend_phase(State(state): State<ApiState>, body: Option<Json<ExtendBody>>) -> ApiResult<TimerUpdate> {
+    state.engine.extend_phase(optional_body(body).minutes).await??;
+    timer_state(State(state)).await
+}
+
+async fn reset_set(State(state): State<ApiState>) -> ApiResult<TimerUpdate> {
+    state.engine.reset_set().await??;
+    timer_state(State(state)).await
+}
+
+async fn skip_break(State(state): State<ApiState>, body: Option<Json<ConfirmationBody>>) -> ApiResult<TimerUpdate> {
+    state.engine.skip_break(optional_body(body).confirmation).await??;
+    timer_state(State(state)).await
+}
+
+async fn skip_status(State(state): State<ApiState>) -> ApiResult<SkipStatus> {
+    Ok(Json(state.engine.skip_status().await?))
+}
+
+#[derive(Debug, Deserialize)]
+struct HistoryQuery {
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+}
+
+async fn history(State(state): State<ApiState>, Query(query): Query<HistoryQuery>) -> ApiResult<Vec<SessionRecord>> {
+    Ok(Json(state.history.query(query.from, query.to)?))
+}
+
+#[derive(Debug, Deserialize)]
+struct DaysQuery {
+    days: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WeeksQuery {
+    weeks: Option<u32>,
+}
+
+async fn daily_stats(State(state): State<ApiState>, Query(query): Query<DaysQuery>) -> ApiResult<Vec<DailyStats>> {
+    Ok(Json(service::daily_stats(&state.history, query.days)?))
+}
+
+async fn weekly_stats(State(state): State<ApiState>, Query(query): Query<WeeksQuery>) -> ApiResult<Vec<WeeklyStats>> {
+    Ok(Json(service::weekly_stats(&state.history, query.weeks)?))
+}
+
+async fn stats_summary(State(state): State<ApiState>) -> ApiResult<StatsSummary> {
+    Ok(Json(service::stats_summary(&state.history)?))
+}
+
+async fn get_settings(State(state): State<ApiState>) -> ApiResult<Settings> {
+    Ok(Json(state.settings.lock().await.get().clone()))
+}
+
+/// Runs a settings change through the same path as the app's own commands.
+async fn modify_settings(
+    state: &ApiState,
+    modify: impl FnOnce(&mut Settings) -> Result<(), String>,
+) -> ApiResult<Settings> {
+    let settings = service::modify_settings(&state.settings, &state.engine, modify)
+        .await
+        .map_err(ApiError::invalid)?;
+    Ok(Json(settings))
+}
+
+/// Hooks run arbitrary commands, so they can only be changed in the app itself;
+/// otherwise anyone holding the API token could run code as the user.
+async fn update_settings(State(state): State<ApiState>, Json(settings): Json<Settings>) -> ApiResult<Settings> {
+    modify_settings(&state, |current| {
+        if settings.hooks != current.hooks {
+            return Err("hooks cannot be changed through the HTTP API".to_string());
+        }
+        *current = settings;
+        Ok(())
+    })
+    .await
+}
+
+async fn list_profiles(State(state): State<ApiState>) -> ApiResult<Vec<Profile>> {
+    Ok(Json(state.settings.lock().await.get().profiles.clone()))
+}
+
+async fn create_profile(State(state): State<ApiState>, Json(profile): Json<Profile>) -> ApiResult<Settings> {
+    modify_settings(&state, |settings| settings.create_profile(profile)).await
+}
+
+async fn update_profile(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+    Json(profile): Json<Profile>,
+) -> ApiResult<Settings> {
+    modify_settings(&state, |settings| settings.update_profile(&name, profile)).await
+}
+
+async fn delete_profile(State(state): State<ApiState>, Path(name): Path<String>) -> ApiResult<Settings> {
+    modify_settings(&state, |settings| settings.delete_profile(&name)).await
+}
+
+async fn switch_profile(State(state): State<ApiState>, Path(name): Path<String>) -> ApiResult<Settings> {
+    modify_settings(&state, |settings| settings.switch_profile(&name)).await
+}
+
+/// Server-Sent Events: `timer_update` and `phase_transition`, named as in the app.
+async fn events(State(state): State<ApiState>) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    // A subscriber that lags behind skips the events it missed.
+    let stream = BroadcastStream::new(state.events.subscribe()).filter_map(|event| {
+        let event = event.ok()?;
+        let data = match &event {
+            EngineEvent::TimerUpdate(update) => Event::default().json_data(update),
+            EngineEvent::PhaseTransition(transition) => Event::default().json_data(transition),
+            _ => return None,
+        };
+        data.ok().map(|data| Ok(data.event(event.name())))
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SystemClock;
+    use crate::engine_actor;
+    use crate::settings::SettingsStore;
+    use crate::timer_engine::TimerEngine;
+    use axum::body::Body;
+    use tokio::sync::Mutex;
+    use tower::ServiceExt;
+
+    const TOKEN: &str = "test-token";
+
+    fn test_router(dir: &std::path::Path) -> Router {
+        let events = Arc::new(BroadcastSink::new(16));
+        let engine = TimerEngine::new(&Profile::default(), Arc::new(SystemClock), events.clone());
+        let (handle, actor) = engine_actor::channel(engine);
+        tokio::spawn(actor.run());
+        router(ApiState {
+            engine: handle,
+            settings: Arc::new(Mutex::new(SettingsStore::load(dir.join("settings.json")))),
+            history: Arc::new(HistoryLog::new(dir.join("history.jsonl"))),
+            events,
+            token: Arc::from(TOKEN),
+        })
+    }
+
+    fn request(method: &str, uri: &str, token: Option<&str>) -> Request {
+        let mut builder = Request::builder().method(method).uri(uri);
+        if let Some(token) = token {
+            builder = builder.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    async fn json(response: Response) -> serde_json::Value {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn requests_without_the_token_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = test_router(dir.path());
+        for token in [None, Some("wrong")] {
+            let response = app.clone().oneshot(request("GET", "/v1/timer", token)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+        // Only the event stream takes the token in the query string
+        let response = app.clone().oneshot(request("GET", "/v1/timer?token=test-token", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = app.clone().oneshot(request("POST", "/v1/timer/start?token=test-token", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = app.oneshot(request("GET", "/v1/events?token=test-token", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn commands_return_the_resulting_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = test_router(dir.path());
+
+        let response = app.clone().oneshot(request("POST", "/v1/timer/start", Some(TOKEN))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(json(response).await["is_running"], true);
+
+        let response = app.oneshot(request("POST", "/v1/timer/skip", Some(TOKEN))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        assert_eq!(json(response).await["denied"]["reason"], "not_in_break");
+    }
+
+    #[tokio::test]
+    async fn hooks_cannot_be_changed() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = test_router(dir.path());
+        let put = |settings: &serde_json::Value| {
+            let mut request = request("PUT", "/v1/settings", Some(TOKEN));
+            request.headers_mut().insert(header::CONTENT_TYPE, "application/json".parse().unwrap());
+            *request.body_mut() = Body::from(settings.to_string());
+            request
+        };
+
+        let mut settings = json(app.clone().oneshot(request("GET", "/v1/settings", Some(TOKEN))).await.unwrap()).await;
+        settings["break_warning_seconds"] = serde_json::json!([30]);
+        let response = app.clone().oneshot(put(&settings)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(json(response).await["break_warning_seconds"], serde_json::json!([30]));
+
+        settings["hooks"]["break_started"] = serde_json::json!(["touch", "/tmp/pwned"]);
+        let response = app.clone().oneshot(put(&settings)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let current = json(app.oneshot(request("GET", "/v1/settings", Some(TOKEN))).await.unwrap()).await;
+        assert_eq!(current["hooks"]["break_started"], serde_json::json!([]));
+    }
+
+    #[tokio::test]
+    async fn every_documented_route_exists() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = test_router(dir.path());
+        let document: serde_json::Value = serde_json::from_str(OPENAPI).unwrap();
+        let paths = document["paths"].as_object().unwrap();
+        assert!(!paths.is_empty());
+
+        for (path, operations) in paths {
+            // The stream never ends; the request would not complete.
+            if path == EVENTS_PATH {
+                continue;
+            }
+            let uri = path.replace("{name}", "NoSuchProfile");
+            let methods = operations.as_object().unwrap().keys();
+            for method in methods.filter(|key| ["get", "post", "put", "delete"].contains(&key.as_str())) {
+                let response = app
+                    .clone()
+                    .oneshot(request(&method.to_uppercase(), &uri, Some(TOKEN)))
+                    .await
+                    .unwrap();
+                assert_ne!(response.status(), StatusCode::NOT_FOUND, "{} {}", method, path);
+                assert_ne!(response.status(), StatusCode::METHOD_NOT_ALLOWED, "{} {}", method, path);
+            }
+        }
+    }
+
+    #[test]
+    fn token_is_generated_once_and_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(TOKEN_FILE_NAME);
+        let token = load_or_create_token(&path).unwrap();
+        assert_eq!(token.len(), TOKEN_BYTES * 2);
+        assert_eq!(load_or_create_token(&path).unwrap(), token);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+}
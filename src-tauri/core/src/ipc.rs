@@ -0,0 +1,269 @@
+use crate::engine_actor::{EngineHandle, EngineStopped};
+use crate::skip_policy::SkipDenied;
+use crate::types::TimerUpdate;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+use tokio::net::{UnixListener, UnixStream};
+
+/// Overrides where the app listens and where clients connect.
+pub const SOCKET_ENV: &str = "TTT_SOCKET";
+pub const SOCKET_FILE_NAME: &str = "timetothink.sock";
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Per-user runtime directory when there is one, the temp directory otherwise
+/// (which macOS already keeps per user).
+pub fn default_socket_path() -> PathBuf {
+    if let Some(path) = std::env::var_os(SOCKET_ENV) {
+        return PathBuf::from(path);
+    }
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir)
+        .join(SOCKET_FILE_NAME)
+}
+
+/// One line of JSON sent by a client; each maps to the Tauri command of the same purpose.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Request {
+    Start,
+    Pause,
+    Reset {
+        #[serde(default)]
+        confirmation: Option<String>,
+    },
+    Skip {
+        #[serde(default)]
+        confirmation: Option<String>,
+    },
+    Status,
+}
+
+/// The reply to a [`Request`]. Successful commands return the timer state they left behind.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Response {
+    Ok {
+        update: TimerUpdate,
+    },
+    Error {
+        message: String,
+        /// Set when a skip was refused by the profile's skip policy.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        denied: Option<SkipDenied>,
+    },
+}
+
+impl From<String> for Response {
+    fn from(message: String) -> Self {
+        Response::Error { message, denied: None }
+    }
+}
+
+impl From<SkipDenied> for Response {
+    fn from(denied: SkipDenied) -> Self {
+        Response::Error { message: denied.to_string(), denied: Some(denied) }
+    }
+}
+
+impl From<EngineStopped> for Response {
+    fn from(stopped: EngineStopped) -> Self {
+        stopped.to_string().into()
+    }
+}
+
+pub async fn dispatch(engine: &EngineHandle, request: Request) -> Response {
+    if let Err(response) = apply(engine, request).await {
+        return response;
+    }
+    match engine.get_update().await {
+        Ok(update) => Response::Ok { update },
+        Err(stopped) => stopped.into(),
+    }
+}
+
+async fn apply(engine: &EngineHandle, request: Request) -> Result<(), Response> {
+    match request {
+        Request::Start => engine.start().await??,
+        Request::Pause => engine.pause().await??,
+        Request::Reset { confirmation } => engine.reset(confirmation).await??,
+        Request::Skip { confirmation } => engine.skip_break(confirmation).await??,
+        Request::Status => {}
+    }
+    Ok(())
+}
+
+/// Listens on `path` until the process exits, answering each request line with a
+/// response line. A socket left behind by a previous run is replaced; one that
+/// still accepts connections means another instance is serving it, and anything
+/// other than a socket is left alone.
+pub async fn serve(path: &Path, engine: EngineHandle) -> std::io::Result<()> {
+    if UnixStream::connect(path).await.is_ok() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AddrInUse,
+            format!("{} is already served by another instance", path.display()),
+        ));
+    }
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if !metadata.file_type().is_socket() => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("{} exists and is not a socket", path.display()),
+            ));
+        }
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+        _ => {}
+    }
+    let listener = bind_private(path)?;
+    println!("[Ipc] Listening on {}", path.display());
+
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                tokio::spawn(handle_connection(stream, engine.clone()));
+            }
+            Err(e) => eprintln!("Failed to accept IPC connection: {}", e),
+        }
+    }
+}
+
+/// Binds the socket inside a directory only the owner can enter, restricts it to
+/// the owner and only then moves it to `path`, replacing any stale socket there.
+/// Other users never get to connect in between.
+fn bind_private(path: &Path) -> std::io::Result<UnixListener> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    let parent = path.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let file_name = path.file_name().unwrap_or(SOCKET_FILE_NAME.as_ref());
+    let staging = parent.join(format!(".{}.{}", file_name.to_string_lossy(), std::process::id()));
+    // Left behind by a run that shared this process id and crashed mid-bind
+    let _ = std::fs::remove_dir_all(&staging);
+    std::fs::DirBuilder::new().mode(0o700).create(&staging)?;
+
+    let staged = staging.join(file_name);
+    let bound = UnixListener::bind(&staged).and_then(|listener| {
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o600))?;
+        std::fs::rename(&staged, path)?;
+        Ok(listener)
+    });
+    let _ = std::fs::remove_dir_all(&staging);
+    bound
+}
+
+async fn handle_connection(stream: UnixStream, engine: EngineHandle) {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = tokio::io::BufReader::new(reader).lines();
+    loop {
+        let line = match lines.next_line().await {
+            Ok(Some(line)) => line,
+            Ok(None) => break,
+            Err(e) => {
+                eprintln!("Failed to read IPC request: {}", e);
+                break;
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => dispatch(&engine, request).await,
+            Err(e) => format!("Invalid request: {}", e).into(),
+        };
+        let Ok(mut reply) = serde_json::to_string(&response) else {
+            break;
+        };
+        reply.push('\n');
+        if writer.write_all(reply.as_bytes()).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Sends one request to the app listening on `path` and waits for its answer.
+pub fn send(path: &Path, request: &Request) -> Result<Response, String> {
+    let mut stream = std::os::unix::net::UnixStream::connect(path)
+        .map_err(|e| format!("TimeToThink is not reachable at {}: {}", path.display(), e))?;
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT)).map_err(|e| e.to_string())?;
+
+    let mut line = serde_json::to_string(request).map_err(|e| e.to_string())?;
+    line.push('\n');
+    stream.write_all(line.as_bytes()).map_err(|e| e.to_string())?;
+
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply).map_err(|e| e.to_string())?;
+    serde_json::from_str(&reply).map_err(|e| format!("Invalid response from TimeToThink: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SystemClock;
+    use crate::engine_actor;
+    use crate::events::NullSink;
+    use crate::settings::Profile;
+    use crate::timer_engine::TimerEngine;
+    use crate::types::TimerState;
+    use std::os::unix::fs::PermissionsExt;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn other_files_at_the_socket_path_are_not_replaced() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SOCKET_FILE_NAME);
+        std::fs::write(&path, "keep me").unwrap();
+        let engine = TimerEngine::new(&Profile::default(), Arc::new(SystemClock), Arc::new(NullSink));
+        let (handle, _actor) = engine_actor::channel(engine);
+
+        let error = serve(&path, handle).await.unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::AlreadyExists);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "keep me");
+    }
+
+    #[test]
+    fn requests_are_tagged_by_command() {
+        let request: Request = serde_json::from_str(r#"{"command":"skip"}"#).unwrap();
+        assert_eq!(request, Request::Skip { confirmation: None });
+        assert_eq!(serde_json::to_string(&Request::Status).unwrap(), r#"{"command":"status"}"#);
+    }
+
+    #[tokio::test]
+    async fn client_drives_the_engine_over_the_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SOCKET_FILE_NAME);
+        let engine = TimerEngine::new(&Profile::default(), Arc::new(SystemClock), Arc::new(NullSink));
+        let (handle, actor) = engine_actor::channel(engine);
+        tokio::spawn(actor.run());
+        let server_path = path.clone();
+        tokio::spawn(async move { serve(&server_path, handle).await });
+        while !path.exists() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let mode = std::fs::symlink_metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let client_path = path.clone();
+        let responses = tokio::task::spawn_blocking(move || {
+            let started = send(&client_path, &Request::Start).unwrap();
+            let skipped = send(&client_path, &Request::Skip { confirmation: None }).unwrap();
+            (started, skipped)
+        })
+        .await
+        .unwrap();
+
+        match responses.0 {
+            Response::Ok { update } => {
+                assert_eq!(update.state, TimerState::Focus);
+                assert!(update.is_running);
+            }
+            other => panic!("unexpected response: {:?}", other),
+        }
+        match responses.1 {
+            Response::Error { denied, .. } => assert_eq!(denied, Some(SkipDenied::NotInBreak)),
+            other => panic!("unexpected response: {:?}", other),
+        }
+    }
+}
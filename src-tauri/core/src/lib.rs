@@ -0,0 +1,31 @@
+//! The TimeToThink timer without a UI: the phase state machine and the engine
+//! driving it, profiles and settings, session history and statistics. Front ends
+//! observe the engine through [`events::EventSink`] and control it through
+//! [`engine_actor::EngineHandle`].
+
+#[cfg(test)]
+mod bindings;
+pub mod clock;
+pub mod commands;
+pub mod engine_actor;
+pub mod events;
+pub mod history;
+pub mod hooks;
+#[cfg(feature = "http-api")]
+pub mod http_api;
+#[cfg(unix)]
+pub mod ipc;
+pub mod service;
+pub mod session_store;
+pub mod settings;
+pub mod shortcuts;
+pub mod skip_policy;
+pub mod state_machine;
+pub mod stats;
+pub mod storage;
+pub mod timer_engine;
+pub mod tray_icon;
+pub mod tray_status;
+pub mod types;
+#[cfg(feature = "webhooks")]
+pub mod webhooks;
@@ -0,0 +1,54 @@
+//! Operations behind the app's commands that take more than a single engine
+//! call, shared by every front end (Tauri commands, HTTP API) so they apply
+//! the same limits and side effects.
+
+use crate::engine_actor::EngineHandle;
+use crate::history::HistoryLog;
+use crate::settings::{Settings, SharedSettings};
+use crate::stats::{self, DailyStats, StatsSummary, WeeklyStats};
+use chrono::{Days, Local};
+
+pub const DEFAULT_STATS_DAYS: u32 = 7;
+pub const DEFAULT_STATS_WEEKS: u32 = 4;
+const MAX_STATS_DAYS: u32 = 366;
+const MAX_STATS_WEEKS: u32 = 104;
+
+/// Per-day totals for the last `days` local days, today included.
+pub fn daily_stats(history: &HistoryLog, days: Option<u32>) -> Result<Vec<DailyStats>, String> {
+    let records = history.query(None, None)?;
+    let today = Local::now().date_naive();
+    let days = days.unwrap_or(DEFAULT_STATS_DAYS).clamp(1, MAX_STATS_DAYS);
+    let first = today.checked_sub_days(Days::new(u64::from(days - 1))).unwrap_or(today);
+    Ok(stats::daily_stats(&records, &Local, first, today))
+}
+
+pub fn weekly_stats(history: &HistoryLog, weeks: Option<u32>) -> Result<Vec<WeeklyStats>, String> {
+    let records = history.query(None, None)?;
+    let weeks = weeks.unwrap_or(DEFAULT_STATS_WEEKS).clamp(1, MAX_STATS_WEEKS);
+    Ok(stats::weekly_stats(&records, &Local, Local::now().date_naive(), weeks))
+}
+
+pub fn stats_summary(history: &HistoryLog) -> Result<StatsSummary, String> {
+    let records = history.query(None, None)?;
+    Ok(stats::summary(&records, &Local, Local::now().date_naive()))
+}
+
+/// Applies `modify` to a copy of the settings, persists the result and hands the
+/// (possibly new) active profile to the engine.
+pub async fn modify_settings(
+    settings_store: &SharedSettings,
+    engine: &EngineHandle,
+    modify: impl FnOnce(&mut Settings) -> Result<(), String>,
+) -> Result<Settings, String> {
+    let settings = {
+        let mut store_guard = settings_store.lock().await;
+        let mut settings = store_guard.get().clone();
+        modify(&mut settings)?;
+        store_guard.update(settings.clone())?;
+        settings
+    };
+    engine
+        .apply_profile(settings.active().clone(), settings.break_warning_seconds.clone())
+        .await??;
+    Ok(settings)
+}
@@ -0,0 +1,76 @@
+use crate::history::PhaseExtension;
+use crate::storage::{read_json, write_json_atomic};
+use crate::types::TimerState;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const SESSION_FILE_NAME: &str = "session.json";
+
+/// Everything needed to resume the engine after a restart or crash.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EngineSnapshot {
+    pub state: TimerState,
+    pub completed_pomodoros: u32,
+    #[serde(default)]
+    pub set_pomodoros: u32,
+    #[serde(default)]
+    pub set_focus_seconds: u64,
+    pub phase_length_ms: u64,
+    /// Wall-clock deadline of the phase, present only while running.
+    pub deadline_unix_ms: Option<u64>,
+    pub paused_remaining_ms: u64,
+    #[serde(default)]
+    pub phase_started_unix_ms: Option<u64>,
+    /// Extensions granted to the current phase.
+    #[serde(default)]
+    pub extensions: Vec<PhaseExtension>,
+    /// Local day the skip counter refers to.
+    #[serde(default)]
+    pub skip_day: Option<NaiveDate>,
+    #[serde(default)]
+    pub skips_on_day: u32,
+    pub saved_at_unix_ms: u64,
+}
+
+/// Destination for engine snapshots.
+pub trait SnapshotStore: Send + Sync {
+    fn save(&self, snapshot: &EngineSnapshot) -> Result<(), String>;
+}
+
+pub struct FileSnapshotStore {
+    path: PathBuf,
+}
+
+impl FileSnapshotStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn load(&self) -> Option<EngineSnapshot> {
+        match read_json(&self.path) {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                eprintln!("Ignoring session file {}: {}", self.path.display(), e);
+                None
+            }
+        }
+    }
+}
+
+impl SnapshotStore for FileSnapshotStore {
+    fn save(&self, snapshot: &EngineSnapshot) -> Result<(), String> {
+        write_json_atomic(&self.path, snapshot)
+    }
+}
+
+pub fn to_unix_ms(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+pub fn from_unix_ms(ms: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_millis(ms)
+}
@@ -0,0 +1,638 @@
+use crate::shortcuts::{Accelerator, ShortcutAction};
+use crate::skip_policy::SkipPolicy;
+use crate::types::PhaseChange;
+use crate::storage::{read_json, write_json_atomic};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::{watch, Mutex};
+use ts_rs::TS;
+
+pub const SETTINGS_FILE_NAME: &str = "settings.json";
+const MIN_PHASE_MINUTES: u32 = 1;
+const MAX_PHASE_MINUTES: u32 = 240;
+const MAX_LONG_BREAK_INTERVAL: u32 = 12;
+const MAX_LONG_BREAK_FOCUS_MINUTES: u32 = 24 * 60;
+const MAX_PROFILE_NAME_LEN: usize = 40;
+const MAX_EXTENSION_MINUTES: u32 = 60;
+const MAX_EXTENSIONS_PER_CYCLE: u32 = 10;
+const MAX_BREAK_WARNINGS: usize = 5;
+const MAX_BREAK_WARNING_SECONDS: u32 = 30 * 60;
+const MIN_HTTP_API_PORT: u16 = 1024;
+const MAX_HOOK_TIMEOUT_SECONDS: u32 = 10 * 60;
+const MAX_WEBHOOKS: usize = 10;
+
+/// Decides when a long break replaces a short one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum LongBreakCadence {
+    /// Every n-th pomodoro of a set ends in a long break.
+    EveryNthPomodoro { interval: u32 },
+    /// A long break follows once this much focus time has accumulated in the set.
+    FocusMinutes { minutes: u32 },
+}
+
+/// How firmly breaks are enforced.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum Enforcement {
+    /// Kiosk lockdown during breaks and no skipping.
+    Strict,
+    /// Kiosk lockdown during breaks; breaks can be skipped after confirmation.
+    #[default]
+    Standard,
+    /// The guard covers the screen but the system stays usable and quitting is allowed.
+    Gentle,
+}
+
+/// Limits on extending a focus phase; counted per focus/break cycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(default)]
+pub struct ExtensionPolicy {
+    /// Length of a single extension.
+    pub minutes: u32,
+    /// Zero disables extensions.
+    pub max_per_cycle: u32,
+    pub max_total_minutes: u32,
+}
+
+impl Default for ExtensionPolicy {
+    fn default() -> Self {
+        Self {
+            minutes: 5,
+            max_per_cycle: 2,
+            max_total_minutes: 10,
+        }
+    }
+}
+
+/// A named rhythm: phase durations, long-break cadence and enforcement.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+pub struct Profile {
+    pub name: String,
+    pub focus_minutes: u32,
+    pub short_break_minutes: u32,
+    pub long_break_minutes: u32,
+    pub long_break_cadence: LongBreakCadence,
+    #[serde(default)]
+    pub enforcement: Enforcement,
+    #[serde(default)]
+    pub extension: ExtensionPolicy,
+    #[serde(default)]
+    pub skip: SkipPolicy,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            name: "Classic".to_string(),
+            focus_minutes: 25,
+            short_break_minutes: 5,
+            long_break_minutes: 25,
+            long_break_cadence: LongBreakCadence::EveryNthPomodoro { interval: 3 },
+            enforcement: Enforcement::Standard,
+            extension: ExtensionPolicy::default(),
+            skip: SkipPolicy::default(),
+        }
+    }
+}
+
+impl Profile {
+    fn built_ins() -> Vec<Profile> {
+        vec![
+            Profile::default(),
+            Profile {
+                name: "Deep Work".to_string(),
+                focus_minutes: 52,
+                short_break_minutes: 17,
+                long_break_minutes: 30,
+                long_break_cadence: LongBreakCadence::EveryNthPomodoro { interval: 3 },
+                enforcement: Enforcement::Standard,
+                extension: ExtensionPolicy::default(),
+                skip: SkipPolicy::default(),
+            },
+            Profile {
+                name: "Ultradian".to_string(),
+                focus_minutes: 90,
+                short_break_minutes: 20,
+                long_break_minutes: 30,
+                long_break_cadence: LongBreakCadence::EveryNthPomodoro { interval: 3 },
+                enforcement: Enforcement::Standard,
+                extension: ExtensionPolicy {
+                    minutes: 10,
+                    max_per_cycle: 1,
+                    max_total_minutes: 10,
+                },
+                skip: SkipPolicy::default(),
+            },
+        ]
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let name = self.name.trim();
+        if name.is_empty() || name.len() > MAX_PROFILE_NAME_LEN || name != self.name {
+            return Err(format!(
+                "profile name must be 1-{} characters without surrounding whitespace (got {:?})",
+                MAX_PROFILE_NAME_LEN, self.name
+            ));
+        }
+
+        let phases = [
+            ("focus_minutes", self.focus_minutes),
+            ("short_break_minutes", self.short_break_minutes),
+            ("long_break_minutes", self.long_break_minutes),
+        ];
+        for (name, minutes) in phases {
+            if !(MIN_PHASE_MINUTES..=MAX_PHASE_MINUTES).contains(&minutes) {
+                return Err(format!(
+                    "{} must be between {} and {} (got {})",
+                    name, MIN_PHASE_MINUTES, MAX_PHASE_MINUTES, minutes
+                ));
+            }
+        }
+
+        match self.long_break_cadence {
+            LongBreakCadence::EveryNthPomodoro { interval } => {
+                if !(1..=MAX_LONG_BREAK_INTERVAL).contains(&interval) {
+                    return Err(format!(
+                        "long break interval must be between 1 and {} (got {})",
+                        MAX_LONG_BREAK_INTERVAL, interval
+                    ));
+                }
+            }
+            LongBreakCadence::FocusMinutes { minutes } => {
+                if !(self.focus_minutes..=MAX_LONG_BREAK_FOCUS_MINUTES).contains(&minutes) {
+                    return Err(format!(
+                        "long break focus minutes must be between focus_minutes ({}) and {} (got {})",
+                        self.focus_minutes, MAX_LONG_BREAK_FOCUS_MINUTES, minutes
+                    ));
+                }
+            }
+        }
+
+        self.skip.validate()?;
+
+        let extension = &self.extension;
+        if !(1..=MAX_EXTENSION_MINUTES).contains(&extension.minutes) {
+            return Err(format!(
+                "extension minutes must be between 1 and {} (got {})",
+                MAX_EXTENSION_MINUTES, extension.minutes
+            ));
+        }
+        if extension.max_per_cycle > MAX_EXTENSIONS_PER_CYCLE {
+            return Err(format!(
+                "at most {} extensions per cycle are allowed (got {})",
+                MAX_EXTENSIONS_PER_CYCLE, extension.max_per_cycle
+            ));
+        }
+        if extension.max_total_minutes > MAX_PHASE_MINUTES {
+            return Err(format!(
+                "extension total must not exceed {} minutes (got {})",
+                MAX_PHASE_MINUTES, extension.max_total_minutes
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// The local HTTP API for scripts and dashboards. Off by default; changes
+/// take effect the next time the app starts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(default)]
+pub struct HttpApiSettings {
+    pub enabled: bool,
+    /// Port on 127.0.0.1.
+    pub port: u16,
+}
+
+impl Default for HttpApiSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 17345,
+        }
+    }
+}
+
+/// Commands run when the timer changes phase, each written as the program
+/// followed by its arguments; an empty list runs nothing. Commands are not
+/// passed through a shell, so use `["sh", "-c", "..."]` for pipes and the like.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(default)]
+pub struct HookSettings {
+    pub focus_started: Vec<String>,
+    pub focus_ended: Vec<String>,
+    pub break_started: Vec<String>,
+    pub break_ended: Vec<String>,
+    pub skipped: Vec<String>,
+    pub reset: Vec<String>,
+    /// A hook still running after this long is killed.
+    pub timeout_seconds: u32,
+}
+
+impl Default for HookSettings {
+    fn default() -> Self {
+        Self {
+            focus_started: Vec::new(),
+            focus_ended: Vec::new(),
+            break_started: Vec::new(),
+            break_ended: Vec::new(),
+            skipped: Vec::new(),
+            reset: Vec::new(),
+            timeout_seconds: 10,
+        }
+    }
+}
+
+impl HookSettings {
+    pub fn command(&self, change: PhaseChange) -> &[String] {
+        match change {
+            PhaseChange::FocusStarted => &self.focus_started,
+            PhaseChange::FocusEnded => &self.focus_ended,
+            PhaseChange::BreakStarted => &self.break_started,
+            PhaseChange::BreakEnded => &self.break_ended,
+            PhaseChange::Skipped => &self.skipped,
+            PhaseChange::Reset => &self.reset,
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if !(1..=MAX_HOOK_TIMEOUT_SECONDS).contains(&self.timeout_seconds) {
+            return Err(format!(
+                "hook timeout must be between 1 and {} seconds (got {})",
+                MAX_HOOK_TIMEOUT_SECONDS, self.timeout_seconds
+            ));
+        }
+        let commands = [
+            ("focus_started", &self.focus_started),
+            ("focus_ended", &self.focus_ended),
+            ("break_started", &self.break_started),
+            ("break_ended", &self.break_ended),
+            ("skipped", &self.skipped),
+            ("reset", &self.reset),
+        ];
+        for (name, command) in commands {
+            if command.first().is_some_and(|program| program.trim().is_empty()) {
+                return Err(format!("{} hook has an empty program name", name));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Global keyboard shortcuts, written as accelerators such as
+/// `CmdOrCtrl+Alt+P`; `null` leaves an action without one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(default)]
+pub struct ShortcutSettings {
+    pub toggle_timer: Option<String>,
+    pub show_window: Option<String>,
+    pub extend_focus: Option<String>,
+}
+
+impl Default for ShortcutSettings {
+    fn default() -> Self {
+        Self {
+            toggle_timer: Some("CmdOrCtrl+Alt+P".to_string()),
+            show_window: Some("CmdOrCtrl+Alt+T".to_string()),
+            extend_focus: Some("CmdOrCtrl+Alt+E".to_string()),
+        }
+    }
+}
+
+impl ShortcutSettings {
+    pub fn accelerator(&self, action: ShortcutAction) -> Option<&str> {
+        match action {
+            ShortcutAction::ToggleTimer => self.toggle_timer.as_deref(),
+            ShortcutAction::ShowWindow => self.show_window.as_deref(),
+            ShortcutAction::ExtendFocus => self.extend_focus.as_deref(),
+        }
+    }
+
+    /// Rejects malformed accelerators and two actions sharing one combination.
+    fn validate(&self) -> Result<(), String> {
+        let mut seen: Vec<(Accelerator, &str)> = Vec::new();
+        for action in ShortcutAction::ALL {
+            let Some(accelerator) = self.accelerator(action) else {
+                continue;
+            };
+            let parsed = Accelerator::parse(accelerator)?;
+            if let Some((_, other)) = seen.iter().find(|(existing, _)| *existing == parsed) {
+                return Err(format!("shortcut {:?} conflicts with {:?}", accelerator, other));
+            }
+            seen.push((parsed, accelerator));
+        }
+        Ok(())
+    }
+}
+
+/// An endpoint told about phase transitions by a signed POST.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+pub struct WebhookTarget {
+    /// A plain `http://` URL; TLS is not built in.
+    pub url: String,
+    /// Key for the `X-TimeToThink-Signature` HMAC.
+    pub secret: String,
+    /// Transitions to send; all of them when empty.
+    #[serde(default)]
+    pub events: Vec<PhaseChange>,
+}
+
+impl WebhookTarget {
+    pub fn wants(&self, change: PhaseChange) -> bool {
+        self.events.is_empty() || self.events.contains(&change)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let host = self.url.strip_prefix("http://").unwrap_or_default();
+        if host.is_empty() || host.starts_with('/') {
+            return Err(format!("webhook URL must start with http:// and name a host (got {:?})", self.url));
+        }
+        if self.secret.trim().is_empty() {
+            return Err(format!("webhook {} needs a secret", self.url));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(default)]
+pub struct Settings {
+    pub active_profile: String,
+    pub profiles: Vec<Profile>,
+    /// Seconds before a break at which a warning is shown.
+    pub break_warning_seconds: Vec<u32>,
+    pub http_api: HttpApiSettings,
+    pub hooks: HookSettings,
+    pub webhooks: Vec<WebhookTarget>,
+    pub shortcuts: ShortcutSettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            active_profile: Profile::default().name,
+            profiles: Profile::built_ins(),
+            break_warning_seconds: vec![60, 10],
+            http_api: HttpApiSettings::default(),
+            hooks: HookSettings::default(),
+            webhooks: Vec::new(),
+            shortcuts: ShortcutSettings::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.profiles.is_empty() {
+            return Err("at least one profile is required".to_string());
+        }
+        for (index, profile) in self.profiles.iter().enumerate() {
+            profile.validate()?;
+            let duplicate = self.profiles[..index]
+                .iter()
+                .any(|other| other.name.eq_ignore_ascii_case(&profile.name));
+            if duplicate {
+                return Err(format!("duplicate profile name {:?}", profile.name));
+            }
+        }
+        if self.profile(&self.active_profile).is_none() {
+            return Err(format!("active profile {:?} does not exist", self.active_profile));
+        }
+
+        if self.break_warning_seconds.len() > MAX_BREAK_WARNINGS {
+            return Err(format!("at most {} break warnings are allowed", MAX_BREAK_WARNINGS));
+        }
+        for &seconds in &self.break_warning_seconds {
+            if !(1..=MAX_BREAK_WARNING_SECONDS).contains(&seconds) {
+                return Err(format!(
+                    "break warnings must be between 1 and {} seconds (got {})",
+                    MAX_BREAK_WARNING_SECONDS, seconds
+                ));
+            }
+        }
+        if self.http_api.port < MIN_HTTP_API_PORT {
+            return Err(format!("HTTP API port must be at least {}", MIN_HTTP_API_PORT));
+        }
+        self.hooks.validate()?;
+        if self.webhooks.len() > MAX_WEBHOOKS {
+            return Err(format!("at most {} webhooks are allowed", MAX_WEBHOOKS));
+        }
+        for (index, webhook) in self.webhooks.iter().enumerate() {
+            webhook.validate()?;
+            if self.webhooks[..index].iter().any(|other| other.url == webhook.url) {
+                return Err(format!("duplicate webhook URL {:?}", webhook.url));
+            }
+        }
+        self.shortcuts.validate()?;
+        Ok(())
+    }
+
+    pub fn profile(&self, name: &str) -> Option<&Profile> {
+        self.profiles.iter().find(|profile| profile.name == name)
+    }
+
+    /// The profile driving the timer. Falls back to the first profile so an
+    /// unvalidated settings value still yields something usable.
+    pub fn active(&self) -> &Profile {
+        self.profile(&self.active_profile)
+            .unwrap_or_else(|| &self.profiles[0])
+    }
+
+    pub fn create_profile(&mut self, profile: Profile) -> Result<(), String> {
+        if self.profile_index(&profile.name).is_some() {
+            return Err(format!("profile {:?} already exists", profile.name));
+        }
+        self.profiles.push(profile);
+        self.validate()
+    }
+
+    /// Replaces the profile called `name`, which may also rename it.
+    pub fn update_profile(&mut self, name: &str, profile: Profile) -> Result<(), String> {
+        let index = self
+            .profile_index(name)
+            .ok_or_else(|| format!("profile {:?} does not exist", name))?;
+        if self.active_profile == self.profiles[index].name {
+            self.active_profile = profile.name.clone();
+        }
+        self.profiles[index] = profile;
+        self.validate()
+    }
+
+    pub fn delete_profile(&mut self, name: &str) -> Result<(), String> {
+        let index = self
+            .profile_index(name)
+            .ok_or_else(|| format!("profile {:?} does not exist", name))?;
+        if self.profiles[index].name == self.active_profile {
+            return Err(format!("cannot delete the active profile {:?}", name));
+        }
+        self.profiles.remove(index);
+        self.validate()
+    }
+
+    pub fn switch_profile(&mut self, name: &str) -> Result<(), String> {
+        let index = self
+            .profile_index(name)
+            .ok_or_else(|| format!("profile {:?} does not exist", name))?;
+        self.active_profile = self.profiles[index].name.clone();
+        Ok(())
+    }
+
+    fn profile_index(&self, name: &str) -> Option<usize> {
+        self.profiles
+            .iter()
+            .position(|profile| profile.name.eq_ignore_ascii_case(name))
+    }
+}
+
+pub struct SettingsStore {
+    path: PathBuf,
+    settings: Settings,
+    changes: watch::Sender<Settings>,
+}
+
+impl SettingsStore {
+    /// Loads settings from `path`, falling back to defaults when the file is
+    /// missing, unreadable or contains values that fail validation.
+    pub fn load(path: PathBuf) -> Self {
+        let settings = match read_settings(&path) {
+            Ok(Some(settings)) => settings,
+            Ok(None) => Settings::default(),
+            Err(e) => {
+                eprintln!("Ignoring settings file {}: {}", path.display(), e);
+                Settings::default()
+            }
+        };
+        let changes = watch::Sender::new(settings.clone());
+        Self { path, settings, changes }
+    }
+
+    pub fn get(&self) -> &Settings {
+        &self.settings
+    }
+
+    /// Receives the settings each time they are saved, so observers such as
+    /// the tray can react without locking the store on every timer update.
+    pub fn subscribe(&self) -> watch::Receiver<Settings> {
+        self.changes.subscribe()
+    }
+
+    pub fn update(&mut self, settings: Settings) -> Result<(), String> {
+        settings.validate()?;
+        write_json_atomic(&self.path, &settings)?;
+        self.settings = settings;
+        self.changes.send_replace(self.settings.clone());
+        Ok(())
+    }
+}
+
+fn read_settings(path: &Path) -> Result<Option<Settings>, String> {
+    let settings: Option<Settings> = read_json(path)?;
+    if let Some(settings) = &settings {
+        settings.validate()?;
+    }
+    Ok(settings)
+}
+
+pub type SharedSettings = Arc<Mutex<SettingsStore>>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(name: &str) -> Profile {
+        Profile {
+            name: name.to_string(),
+            ..Profile::default()
+        }
+    }
+
+    #[test]
+    fn default_settings_are_valid() {
+        let settings = Settings::default();
+        settings.validate().unwrap();
+        assert_eq!(settings.active().name, "Classic");
+    }
+
+    #[test]
+    fn create_profile_rejects_duplicate_names() {
+        let mut settings = Settings::default();
+        assert!(settings.create_profile(profile("deep work")).is_err());
+        settings.create_profile(profile("Sprint")).unwrap();
+        assert!(settings.profile("Sprint").is_some());
+    }
+
+    #[test]
+    fn renaming_the_active_profile_keeps_it_active() {
+        let mut settings = Settings::default();
+        settings.update_profile("classic", profile("Pomodoro")).unwrap();
+        assert_eq!(settings.active_profile, "Pomodoro");
+        assert_eq!(settings.active().name, "Pomodoro");
+    }
+
+    #[test]
+    fn active_profile_cannot_be_deleted() {
+        let mut settings = Settings::default();
+        assert!(settings.delete_profile("Classic").is_err());
+        settings.switch_profile("ultradian").unwrap();
+        assert_eq!(settings.active_profile, "Ultradian");
+        settings.delete_profile("Classic").unwrap();
+        assert_eq!(settings.profiles.len(), 2);
+    }
+
+    #[test]
+    fn invalid_profiles_are_rejected() {
+        let mut settings = Settings::default();
+        let zero_focus = Profile { focus_minutes: 0, ..profile("Broken") };
+        assert!(settings.create_profile(zero_focus).is_err());
+        let cadence_below_focus = Profile {
+            long_break_cadence: LongBreakCadence::FocusMinutes { minutes: 10 },
+            ..profile("Broken")
+        };
+        assert!(cadence_below_focus.validate().is_err());
+    }
+
+    #[test]
+    fn invalid_hooks_are_rejected() {
+        let mut settings = Settings::default();
+        settings.hooks.break_started = vec![" ".to_string(), "--flag".to_string()];
+        assert!(settings.validate().is_err());
+        settings.hooks.break_started = vec!["notify-send".to_string()];
+        assert!(settings.validate().is_ok());
+        settings.hooks.timeout_seconds = 0;
+        assert!(settings.validate().is_err());
+    }
+
+    #[test]
+    fn conflicting_shortcuts_are_rejected() {
+        let mut settings = Settings::default();
+        assert!(settings.validate().is_ok());
+        settings.shortcuts.extend_focus = Some("cmdorctrl+alt+p".to_string());
+        let error = settings.validate().unwrap_err();
+        assert!(error.contains("conflicts with \"CmdOrCtrl+Alt+P\""), "{}", error);
+        settings.shortcuts.extend_focus = None;
+        assert!(settings.validate().is_ok());
+        settings.shortcuts.show_window = Some("Ctrl+Nope+T".to_string());
+        assert!(settings.validate().is_err());
+    }
+
+    #[test]
+    fn saved_settings_reach_subscribers() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = SettingsStore::load(dir.path().join(SETTINGS_FILE_NAME));
+        let mut changes = store.subscribe();
+        assert!(!changes.has_changed().unwrap());
+
+        let mut rejected = store.get().clone();
+        rejected.hooks.timeout_seconds = 0;
+        assert!(store.update(rejected).is_err());
+        assert!(!changes.has_changed().unwrap());
+
+        let mut settings = store.get().clone();
+        settings.switch_profile("Deep Work").unwrap();
+        store.update(settings).unwrap();
+        assert!(changes.has_changed().unwrap());
+        assert_eq!(changes.borrow_and_update().active_profile, "Deep Work");
+    }
+}
@@ -0,0 +1,160 @@
+//! Global keyboard shortcuts: the actions they trigger and the accelerator
+//! syntax used in the settings, e.g. `CmdOrCtrl+Alt+P`. Registering them with
+//! the OS is left to the app; this module only decides whether two
+//! accelerators mean the same key combination.
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum ShortcutAction {
+    /// Starts the timer when it is stopped and pauses it when it is running.
+    ToggleTimer,
+    ShowWindow,
+    /// Extends the current focus phase by the profile's extension length.
+    ExtendFocus,
+}
+
+impl ShortcutAction {
+    pub const ALL: [ShortcutAction; 3] = [
+        ShortcutAction::ToggleTimer,
+        ShortcutAction::ShowWindow,
+        ShortcutAction::ExtendFocus,
+    ];
+}
+
+/// A shortcut as the settings describe it and as the OS took it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+pub struct ShortcutBinding {
+    pub action: ShortcutAction,
+    /// `None` when the action has no shortcut.
+    pub accelerator: Option<String>,
+    pub registered: bool,
+    /// Why registration failed, typically because another app holds the keys.
+    pub error: Option<String>,
+}
+
+const CONTROL: u8 = 1;
+const ALT: u8 = 1 << 1;
+const SHIFT: u8 = 1 << 2;
+const SUPER: u8 = 1 << 3;
+
+/// `CmdOrCtrl` is Command on macOS and Control elsewhere.
+#[cfg(target_os = "macos")]
+const COMMAND_OR_CONTROL: u8 = SUPER;
+#[cfg(not(target_os = "macos"))]
+const COMMAND_OR_CONTROL: u8 = CONTROL;
+
+/// An accelerator reduced to what the OS sees, so spelling differences such
+/// as `Ctrl+Shift+P` and `shift+control+p` compare equal.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Accelerator {
+    modifiers: u8,
+    key: String,
+}
+
+impl Accelerator {
+    /// Parses modifiers followed by one key, joined with `+`. Only the
+    /// function keys may be used without a modifier, so a shortcut never
+    /// swallows ordinary typing.
+    pub fn parse(accelerator: &str) -> Result<Self, String> {
+        let invalid = |reason: &str| format!("invalid shortcut {:?}: {}", accelerator, reason);
+        let mut tokens: Vec<&str> = accelerator.split('+').map(str::trim).collect();
+        let key = tokens.pop().filter(|key| !key.is_empty()).ok_or_else(|| invalid("no key"))?;
+
+        let mut modifiers = 0;
+        for token in tokens {
+            let modifier = match token.to_ascii_uppercase().as_str() {
+                "CONTROL" | "CTRL" => CONTROL,
+                "ALT" | "OPTION" => ALT,
+                "SHIFT" => SHIFT,
+                "SUPER" | "COMMAND" | "CMD" => SUPER,
+                "COMMANDORCONTROL" | "COMMANDORCTRL" | "CMDORCTRL" | "CMDORCONTROL" => COMMAND_OR_CONTROL,
+                "" => return Err(invalid("empty modifier")),
+                _ => return Err(invalid(&format!("unknown modifier {:?}", token))),
+            };
+            if modifiers & modifier != 0 {
+                return Err(invalid(&format!("{} is repeated", token)));
+            }
+            modifiers |= modifier;
+        }
+
+        let key = normalize_key(key);
+        if modifiers == 0 && !is_function_key(&key) {
+            return Err(invalid("needs at least one modifier"));
+        }
+        Ok(Self { modifiers, key })
+    }
+}
+
+fn normalize_key(key: &str) -> String {
+    let upper = key.to_ascii_uppercase();
+    let alias = match upper.as_str() {
+        "`" => "BACKQUOTE",
+        "\\" => "BACKSLASH",
+        "[" => "BRACKETLEFT",
+        "]" => "BRACKETRIGHT",
+        "," => "COMMA",
+        "=" => "EQUAL",
+        "-" => "MINUS",
+        "." => "PERIOD",
+        "'" => "QUOTE",
+        ";" => "SEMICOLON",
+        "/" => "SLASH",
+        "ESC" => "ESCAPE",
+        "RETURN" => "ENTER",
+        "UP" => "ARROWUP",
+        "DOWN" => "ARROWDOWN",
+        "LEFT" => "ARROWLEFT",
+        "RIGHT" => "ARROWRIGHT",
+        _ => "",
+    };
+    if !alias.is_empty() {
+        return alias.to_string();
+    }
+    match upper.as_bytes() {
+        [letter] if letter.is_ascii_uppercase() => format!("KEY{}", upper),
+        [digit] if digit.is_ascii_digit() => format!("DIGIT{}", upper),
+        _ => upper,
+    }
+}
+
+fn is_function_key(key: &str) -> bool {
+    key.strip_prefix('F')
+        .and_then(|number| number.parse::<u8>().ok())
+        .is_some_and(|number| (1..=24).contains(&number))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spellings_of_the_same_keys_are_equal() {
+        let a = Accelerator::parse("Ctrl+Shift+P").unwrap();
+        assert_eq!(a, Accelerator::parse("shift + control + p").unwrap());
+        assert_eq!(a, Accelerator::parse("Control+Shift+KeyP").unwrap());
+        assert_ne!(a, Accelerator::parse("Ctrl+Alt+P").unwrap());
+        assert_eq!(Accelerator::parse("Alt+Up").unwrap(), Accelerator::parse("Option+ArrowUp").unwrap());
+    }
+
+    #[test]
+    fn cmd_or_ctrl_follows_the_platform() {
+        let native = if cfg!(target_os = "macos") { "Cmd+Alt+T" } else { "Ctrl+Alt+T" };
+        assert_eq!(Accelerator::parse("CmdOrCtrl+Alt+T").unwrap(), Accelerator::parse(native).unwrap());
+    }
+
+    #[test]
+    fn malformed_shortcuts_are_rejected() {
+        assert!(Accelerator::parse("").is_err());
+        assert!(Accelerator::parse("Ctrl+").is_err());
+        assert!(Accelerator::parse("Ctrl++P").is_err());
+        assert!(Accelerator::parse("Hyper+P").is_err());
+        assert!(Accelerator::parse("Ctrl+Ctrl+P").is_err());
+        // Bare keys would fire while typing; function keys are fine
+        assert!(Accelerator::parse("P").is_err());
+        assert!(Accelerator::parse("F9").is_ok());
+        assert!(Accelerator::parse("F25").is_err());
+    }
+}
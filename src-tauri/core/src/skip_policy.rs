@@ -0,0 +1,134 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use ts_rs::TS;
+
+const MAX_DAILY_SKIP_QUOTA: u32 = 100;
+const MAX_CONFIRMATION_PHRASE_LEN: usize = 200;
+
+/// Conditions a break must meet before it can be skipped.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(default)]
+pub struct SkipPolicy {
+    /// Skips allowed per local calendar day; `None` means unlimited.
+    pub daily_quota: Option<u32>,
+    /// Percentage of the break that must have elapsed before skipping unlocks.
+    pub min_elapsed_percent: u32,
+    /// When set, this phrase has to be typed to confirm a skip.
+    pub confirmation_phrase: Option<String>,
+}
+
+impl SkipPolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.daily_quota.is_some_and(|quota| quota > MAX_DAILY_SKIP_QUOTA) {
+            return Err(format!("daily skip quota must not exceed {}", MAX_DAILY_SKIP_QUOTA));
+        }
+        if self.min_elapsed_percent > 100 {
+            return Err(format!(
+                "min_elapsed_percent must be between 0 and 100 (got {})",
+                self.min_elapsed_percent
+            ));
+        }
+        if let Some(phrase) = &self.confirmation_phrase {
+            if phrase.trim().is_empty() || phrase.len() > MAX_CONFIRMATION_PHRASE_LEN {
+                return Err(format!(
+                    "confirmation phrase must be 1-{} characters",
+                    MAX_CONFIRMATION_PHRASE_LEN
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Why a skip was refused. Serialized with a `reason` tag so front ends can react to each case.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum SkipDenied {
+    NotInBreak,
+    DisabledByProfile,
+    TooEarly { unlocks_in_seconds: u32 },
+    QuotaExhausted { used: u32, quota: u32 },
+    ConfirmationRequired { phrase: String },
+}
+
+impl std::fmt::Display for SkipDenied {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SkipDenied::NotInBreak => write!(f, "There is no break to skip"),
+            SkipDenied::DisabledByProfile => write!(f, "Skipping breaks is disabled by the active profile"),
+            SkipDenied::TooEarly { unlocks_in_seconds } => {
+                write!(f, "Skipping unlocks in {} seconds", unlocks_in_seconds)
+            }
+            SkipDenied::QuotaExhausted { used, quota } => {
+                write!(f, "Daily skip quota used up ({} of {})", used, quota)
+            }
+            SkipDenied::ConfirmationRequired { phrase } => {
+                write!(f, "Type \"{}\" to confirm skipping", phrase)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SkipDenied {}
+
+/// What the front end needs to present the skip control.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+pub struct SkipStatus {
+    /// Why the skip button should be disabled right now, if it should.
+    pub denied: Option<SkipDenied>,
+    /// `None` when the quota is unlimited.
+    pub skips_left_today: Option<u32>,
+    pub confirmation_phrase: Option<String>,
+}
+
+/// The engine's view of the current break when judging a skip.
+pub struct SkipContext {
+    pub in_break: bool,
+    pub skipping_disabled: bool,
+    pub elapsed: Duration,
+    pub phase_length: Duration,
+    pub skips_today: u32,
+}
+
+/// Checks a skip against `policy`: the preconditions, then, if the policy
+/// sets a confirmation phrase, that `confirmation` matches it. A missing
+/// confirmation is refused with `ConfirmationRequired`, so front ends deciding
+/// whether to offer the skip button should call `check_preconditions` instead.
+pub fn check(policy: &SkipPolicy, context: &SkipContext, confirmation: Option<&str>) -> Result<(), SkipDenied> {
+    check_preconditions(policy, context)?;
+    if let Some(phrase) = &policy.confirmation_phrase {
+        let confirmed = confirmation.is_some_and(|typed| typed.trim() == phrase.trim());
+        if !confirmed {
+            return Err(SkipDenied::ConfirmationRequired { phrase: phrase.clone() });
+        }
+    }
+    Ok(())
+}
+
+/// Everything `check` requires except the confirmation phrase.
+pub fn check_preconditions(policy: &SkipPolicy, context: &SkipContext) -> Result<(), SkipDenied> {
+    if !context.in_break {
+        return Err(SkipDenied::NotInBreak);
+    }
+    if context.skipping_disabled {
+        return Err(SkipDenied::DisabledByProfile);
+    }
+
+    let required = context.phase_length.mul_f64(f64::from(policy.min_elapsed_percent) / 100.0);
+    if context.elapsed < required {
+        let unlocks_in = required - context.elapsed;
+        return Err(SkipDenied::TooEarly {
+            unlocks_in_seconds: unlocks_in.as_millis().div_ceil(1000) as u32,
+        });
+    }
+
+    if let Some(quota) = policy.daily_quota {
+        if context.skips_today >= quota {
+            return Err(SkipDenied::QuotaExhausted {
+                used: context.skips_today,
+                quota,
+            });
+        }
+    }
+    Ok(())
+}
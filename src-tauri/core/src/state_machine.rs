@@ -0,0 +1,634 @@
+use crate::history::{PhaseExtension, SessionOutcome, SessionRecord};
+use crate::settings::{LongBreakCadence, Profile};
+use crate::types::{PhaseChange, TimerState};
+use std::time::{Duration, SystemTime};
+
+/// Deadlines missed by more than this are treated as the machine having been
+/// asleep (or the process suspended) rather than ordinary tick jitter.
+pub const MISSED_DEADLINE_GRACE: Duration = Duration::from_secs(15);
+
+/// Phase lengths and long-break cadence the machine runs with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rules {
+    pub focus: Duration,
+    pub short_break: Duration,
+    pub long_break: Duration,
+    pub long_break_cadence: LongBreakCadence,
+}
+
+/// Phase lengths that stand in for those of every profile, such as the
+/// seconds-long phases of the app's test mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PhaseLengths {
+    pub focus: Duration,
+    pub short_break: Duration,
+    pub long_break: Duration,
+}
+
+impl Rules {
+    pub fn from_profile(profile: &Profile) -> Self {
+        Self {
+            focus: Duration::from_secs(u64::from(profile.focus_minutes) * 60),
+            short_break: Duration::from_secs(u64::from(profile.short_break_minutes) * 60),
+            long_break: Duration::from_secs(u64::from(profile.long_break_minutes) * 60),
+            long_break_cadence: profile.long_break_cadence,
+        }
+    }
+
+    /// These rules with `lengths` in place of the profile's phase lengths.
+    pub fn with_lengths(self, lengths: PhaseLengths) -> Self {
+        Self {
+            focus: lengths.focus,
+            short_break: lengths.short_break,
+            long_break: lengths.long_break,
+            ..self
+        }
+    }
+
+    pub fn phase_length(&self, phase: TimerState) -> Duration {
+        match phase {
+            TimerState::Focus => self.focus,
+            TimerState::ShortBreak => self.short_break,
+            TimerState::LongBreak => self.long_break,
+        }
+    }
+
+    /// The break that follows a focus phase once the set has reached these totals.
+    pub fn break_for_set(&self, set_pomodoros: u32, set_focus_seconds: u64) -> TimerState {
+        let long_break_due = match self.long_break_cadence {
+            LongBreakCadence::EveryNthPomodoro { interval } => set_pomodoros >= interval.max(1),
+            LongBreakCadence::FocusMinutes { minutes } => set_focus_seconds >= u64::from(minutes) * 60,
+        };
+        if long_break_due {
+            TimerState::LongBreak
+        } else {
+            TimerState::ShortBreak
+        }
+    }
+}
+
+/// Everything the transition table reads and writes.
+///
+/// While running, only the wall-clock deadline of the phase is stored (start
+/// time plus phase length, pushed back by any pauses) and the remaining time is
+/// derived from it, so late or missed ticks never cause drift. Wall-clock time
+/// is used instead of `Instant` because the monotonic clock stops while macOS
+/// is asleep.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MachineState {
+    pub phase: TimerState,
+    /// Lifetime count; unaffected by resets of the current set.
+    pub completed_pomodoros: u32,
+    /// Pomodoros and focus time since the last long break.
+    pub set_pomodoros: u32,
+    pub set_focus_seconds: u64,
+    /// Includes any extensions granted to the current phase.
+    pub phase_length: Duration,
+    /// Set while the timer is running.
+    pub deadline: Option<SystemTime>,
+    /// Time left in the phase while paused.
+    pub paused_remaining: Duration,
+    /// When the current phase first started running; `None` until it does.
+    pub phase_started_at: Option<SystemTime>,
+    pub extensions: Vec<PhaseExtension>,
+}
+
+impl MachineState {
+    /// An idle, full-length focus phase at the start of a set.
+    pub fn new(rules: &Rules) -> Self {
+        let phase_length = rules.focus;
+        Self {
+            phase: TimerState::Focus,
+            completed_pomodoros: 0,
+            set_pomodoros: 0,
+            set_focus_seconds: 0,
+            phase_length,
+            deadline: None,
+            paused_remaining: phase_length,
+            phase_started_at: None,
+            extensions: Vec::new(),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.deadline.is_some()
+    }
+
+    /// The guard is shown exactly while a break is counting down.
+    pub fn guard_visible(&self) -> bool {
+        self.phase.is_break() && self.is_running()
+    }
+
+    pub fn remaining_at(&self, now: SystemTime) -> Duration {
+        match self.deadline {
+            // Clamp to the phase length in case the wall clock was set backwards.
+            Some(deadline) => deadline
+                .duration_since(now)
+                .unwrap_or(Duration::ZERO)
+                .min(self.phase_length),
+            None => self.paused_remaining,
+        }
+    }
+
+    /// True for an idle phase that still has its full, unextended length ahead of it.
+    pub fn is_untouched(&self) -> bool {
+        !self.is_running() && self.paused_remaining == self.phase_length && self.extensions.is_empty()
+    }
+
+    /// The break the current focus phase will end in if it runs to completion.
+    pub fn upcoming_break(&self, rules: &Rules) -> TimerState {
+        rules.break_for_set(self.set_pomodoros + 1, self.set_focus_seconds + self.phase_length.as_secs())
+    }
+
+    /// Switches to `phase` with a full-length phase. With a start time the phase
+    /// runs with a deadline anchored there; without one it waits paused.
+    pub fn enter_phase(&mut self, rules: &Rules, phase: TimerState, start: Option<SystemTime>) {
+        self.phase = phase;
+        self.phase_length = rules.phase_length(phase);
+        self.deadline = start.map(|start| start + self.phase_length);
+        self.paused_remaining = self.phase_length;
+        self.phase_started_at = start;
+        self.extensions.clear();
+    }
+
+    fn clear_set(&mut self) {
+        self.set_pomodoros = 0;
+        self.set_focus_seconds = 0;
+    }
+
+    /// The history record for the current phase ending at `ended_at`. Phases
+    /// that never started running leave no trace.
+    fn record(&self, outcome: SessionOutcome, ended_at: SystemTime) -> Option<SessionRecord> {
+        let started_at = self.phase_started_at?;
+        let actual = self.phase_length.saturating_sub(self.remaining_at(ended_at));
+        let extended_seconds: u64 = self.extensions.iter().map(|e| e.seconds).sum();
+        Some(SessionRecord {
+            phase: self.phase,
+            outcome,
+            planned_seconds: self.phase_length.as_secs().saturating_sub(extended_seconds),
+            actual_seconds: actual.as_secs(),
+            started_at: started_at.into(),
+            ended_at: ended_at.into(),
+            extensions: self.extensions.clone(),
+        })
+    }
+}
+
+/// Inputs to the transition table. Policy checks (skip quotas, extension
+/// limits, lockdown) happen before an event is fed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    Start { now: SystemTime },
+    Pause { now: SystemTime },
+    /// Back to an idle focus phase and a new set.
+    Reset { now: SystemTime },
+    /// Ends a break early; ignored outside breaks.
+    Skip { now: SystemTime },
+    /// Lengthens the current focus phase; ignored outside focus.
+    Extend { now: SystemTime, seconds: u64 },
+    /// Starts a new long-break set without touching the current phase.
+    ResetSet,
+    /// Time has passed; ends the phase once its deadline is reached.
+    Tick { now: SystemTime },
+}
+
+/// Side effects of a transition, to be carried out in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Effect {
+    ShowGuard,
+    HideGuard,
+    Record(SessionRecord),
+    Phase(PhaseChange),
+    /// Front ends should be sent the new state.
+    Notify,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transition {
+    pub state: MachineState,
+    pub effects: Vec<Effect>,
+}
+
+/// The single place where the timer changes phase.
+///
+/// Guard effects are derived by comparing [`MachineState::guard_visible`]
+/// before and after, so the guard is shown exactly while a break runs no
+/// matter which event got it there; phase changes are derived the same way.
+/// Events that change nothing return the state unchanged and no effects.
+///
+/// When a deadline has passed by more than [`MISSED_DEADLINE_GRACE`], the
+/// time since the deadline is treated as rest:
+/// - a finished focus phase is still credited; if the time away already
+///   covers the break that would follow, the break is considered taken and
+///   the machine waits in a fresh, paused focus phase, otherwise the break
+///   starts with only its remaining portion left;
+/// - a finished break ends and the machine waits in a fresh, paused focus
+///   phase instead of silently starting a focus session nobody is watching.
+pub fn transition(state: &MachineState, rules: &Rules, event: Event) -> Transition {
+    let mut next = state.clone();
+    let mut effects = Vec::new();
+    // Set when the phase ran its course, as opposed to being skipped or reset
+    let mut finished = false;
+
+    let changed = match event {
+        Event::Start { now } => {
+            if next.is_running() {
+                false
+            } else if next.paused_remaining.is_zero() {
+                // Paused on its last second: the phase is over, so finish it and start the next
+                finish_phase(&mut next, rules, now, Duration::ZERO, &mut effects);
+                finished = true;
+                true
+            } else {
+                next.deadline = Some(now + next.paused_remaining);
+                next.phase_started_at.get_or_insert(now);
+                true
+            }
+        }
+        Event::Pause { now } => {
+            if next.is_running() {
+                next.paused_remaining = next.remaining_at(now);
+                next.deadline = None;
+                true
+            } else {
+                false
+            }
+        }
+        Event::Reset { now } => {
+            effects.extend(next.record(SessionOutcome::Reset, now).map(Effect::Record));
+            next.enter_phase(rules, TimerState::Focus, None);
+            next.clear_set();
+            // Resetting an untouched focus phase at the start of a set does nothing
+            next != *state
+        }
+        Event::Skip { now } => {
+            if next.phase.is_break() {
+                effects.extend(next.record(SessionOutcome::Skipped, now).map(Effect::Record));
+                let start = next.deadline.map(|_| now);
+                next.enter_phase(rules, TimerState::Focus, start);
+                true
+            } else {
+                false
+            }
+        }
+        Event::Extend { now, seconds } => {
+            if next.phase == TimerState::Focus {
+                let extra = Duration::from_secs(seconds);
+                next.phase_length += extra;
+                match &mut next.deadline {
+                    Some(deadline) => *deadline += extra,
+                    None => next.paused_remaining += extra,
+                }
+                next.extensions.push(PhaseExtension { at: now.into(), seconds });
+                true
+            } else {
+                false
+            }
+        }
+        Event::ResetSet => {
+            next.clear_set();
+            next != *state
+        }
+        Event::Tick { now } => match next.deadline {
+            Some(deadline) if now >= deadline => {
+                let overdue = now.duration_since(deadline).unwrap_or(Duration::ZERO);
+                finish_phase(&mut next, rules, deadline, overdue, &mut effects);
+                finished = true;
+                true
+            }
+            _ => false,
+        },
+    };
+
+    if changed {
+        match (state.guard_visible(), next.guard_visible()) {
+            (false, true) => effects.push(Effect::ShowGuard),
+            (true, false) => effects.push(Effect::HideGuard),
+            _ => {}
+        }
+        effects.extend(phase_changes(state, &next, event, finished).into_iter().map(Effect::Phase));
+        effects.push(Effect::Notify);
+    }
+    Transition { state: next, effects }
+}
+
+/// What `event` did to the phase, in the order it happened: how the old phase
+/// ended, if it did, then whether a new one started running. `finished` says
+/// the old phase ran its course.
+fn phase_changes(before: &MachineState, after: &MachineState, event: Event, finished: bool) -> Vec<PhaseChange> {
+    let mut changes = Vec::new();
+    match event {
+        Event::Reset { .. } => changes.push(PhaseChange::Reset),
+        Event::Skip { .. } => changes.push(PhaseChange::Skipped),
+        _ if finished && before.phase.is_break() => changes.push(PhaseChange::BreakEnded),
+        _ if finished => changes.push(PhaseChange::FocusEnded),
+        _ => {}
+    }
+    let started = after.is_running()
+        && (after.phase != before.phase || after.phase_started_at != before.phase_started_at);
+    if started {
+        changes.push(if after.phase.is_break() {
+            PhaseChange::BreakStarted
+        } else {
+            PhaseChange::FocusStarted
+        });
+    }
+    changes
+}
+
+fn finish_phase(
+    state: &mut MachineState,
+    rules: &Rules,
+    deadline: SystemTime,
+    overdue: Duration,
+    effects: &mut Vec<Effect>,
+) {
+    let missed = overdue > MISSED_DEADLINE_GRACE;
+    effects.extend(state.record(SessionOutcome::Completed, deadline).map(Effect::Record));
+
+    if state.phase.is_break() {
+        // Chain the next focus phase off the old deadline so ticks never accumulate drift.
+        let start = if missed { None } else { Some(deadline) };
+        state.enter_phase(rules, TimerState::Focus, start);
+        return;
+    }
+
+    state.completed_pomodoros += 1;
+    state.set_pomodoros += 1;
+    state.set_focus_seconds += state.phase_length.as_secs();
+    let next_break = rules.break_for_set(state.set_pomodoros, state.set_focus_seconds);
+    if next_break == TimerState::LongBreak {
+        state.clear_set();
+    }
+
+    let break_length = rules.phase_length(next_break);
+    if missed && overdue >= break_length {
+        effects.push(Effect::Record(SessionRecord {
+            phase: next_break,
+            outcome: SessionOutcome::Completed,
+            planned_seconds: break_length.as_secs(),
+            actual_seconds: break_length.as_secs(),
+            started_at: deadline.into(),
+            ended_at: (deadline + break_length).into(),
+            extensions: Vec::new(),
+        }));
+        state.enter_phase(rules, TimerState::Focus, None);
+        return;
+    }
+
+    state.enter_phase(rules, next_break, Some(deadline));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[derive(Debug, Clone)]
+    enum Step {
+        Start,
+        Pause,
+        Reset,
+        Skip,
+        Extend(u64),
+        ResetSet,
+        Advance(u64),
+    }
+
+    impl Step {
+        /// The event for this step at `now`, which `Advance` moves forward.
+        fn event(self, now: &mut SystemTime) -> Event {
+            let at = *now;
+            match self {
+                Step::Start => Event::Start { now: at },
+                Step::Pause => Event::Pause { now: at },
+                Step::Reset => Event::Reset { now: at },
+                Step::Skip => Event::Skip { now: at },
+                Step::Extend(seconds) => Event::Extend { now: at, seconds },
+                Step::ResetSet => Event::ResetSet,
+                Step::Advance(seconds) => {
+                    *now += Duration::from_secs(seconds);
+                    Event::Tick { now: *now }
+                }
+            }
+        }
+    }
+
+    fn step() -> impl Strategy<Value = Step> {
+        prop_oneof![
+            3 => Just(Step::Start),
+            1 => Just(Step::Pause),
+            1 => Just(Step::Reset),
+            1 => Just(Step::Skip),
+            1 => (1u64..=10).prop_map(|minutes| Step::Extend(minutes * 60)),
+            1 => Just(Step::ResetSet),
+            6 => (0u64..=3_600).prop_map(Step::Advance),
+        ]
+    }
+
+    fn rules() -> impl Strategy<Value = Rules> {
+        let cadence = prop_oneof![
+            (1u32..=5).prop_map(|interval| LongBreakCadence::EveryNthPomodoro { interval }),
+            (1u32..=200).prop_map(|minutes| LongBreakCadence::FocusMinutes { minutes }),
+        ];
+        (1u64..=90, 1u64..=30, 1u64..=45, cadence).prop_map(|(focus, short, long, long_break_cadence)| Rules {
+            focus: Duration::from_secs(focus * 60),
+            short_break: Duration::from_secs(short * 60),
+            long_break: Duration::from_secs(long * 60),
+            long_break_cadence,
+        })
+    }
+
+    fn start_time() -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+    }
+
+    proptest! {
+        #[test]
+        fn invariants_hold_for_any_event_sequence(rules in rules(), steps in prop::collection::vec(step(), 1..120)) {
+            let mut now = start_time();
+            let mut state = MachineState::new(&rules);
+            let mut guard_shown = false;
+
+            for step in steps {
+                let event = step.event(&mut now);
+                let Transition { state: next, effects } = transition(&state, &rules, event);
+
+                for effect in &effects {
+                    match effect {
+                        Effect::ShowGuard => {
+                            prop_assert!(!guard_shown, "guard shown twice");
+                            guard_shown = true;
+                        }
+                        Effect::HideGuard => {
+                            prop_assert!(guard_shown, "guard hidden while not shown");
+                            guard_shown = false;
+                        }
+                        Effect::Record(record) => {
+                            prop_assert!(record.ended_at >= record.started_at);
+                            let extended: u64 = record.extensions.iter().map(|e| e.seconds).sum();
+                            prop_assert!(record.actual_seconds <= record.planned_seconds + extended);
+                        }
+                        Effect::Phase(PhaseChange::FocusStarted) => {
+                            prop_assert!(next.is_running() && next.phase == TimerState::Focus);
+                        }
+                        Effect::Phase(PhaseChange::BreakStarted) => {
+                            prop_assert!(next.guard_visible());
+                        }
+                        Effect::Phase(PhaseChange::BreakEnded | PhaseChange::Skipped) => {
+                            prop_assert!(state.phase.is_break() && next.phase == TimerState::Focus);
+                        }
+                        Effect::Phase(PhaseChange::FocusEnded) => {
+                            prop_assert_eq!(state.phase, TimerState::Focus);
+                            prop_assert_eq!(next.completed_pomodoros, state.completed_pomodoros + 1);
+                        }
+                        Effect::Phase(PhaseChange::Reset) => {
+                            prop_assert!(!next.is_running() && next.phase == TimerState::Focus);
+                        }
+                        Effect::Notify => {}
+                    }
+                }
+                prop_assert_eq!(effects.last() == Some(&Effect::Notify), !effects.is_empty());
+                // Events that change nothing have no effects, and the other way round
+                prop_assert_eq!(effects.is_empty(), next == state, "{:?} gave {:?}", event, effects);
+
+                // The guard is visible if and only if a break is running.
+                prop_assert_eq!(guard_shown, next.guard_visible());
+                prop_assert!(next.remaining_at(now) <= next.phase_length);
+                prop_assert!(next.completed_pomodoros >= state.completed_pomodoros);
+                // Every credited pomodoro is announced, however the focus phase was left
+                let focus_ended = effects.contains(&Effect::Phase(PhaseChange::FocusEnded));
+                prop_assert_eq!(focus_ended, next.completed_pomodoros > state.completed_pomodoros);
+                if next.phase == TimerState::LongBreak && state.phase == TimerState::Focus {
+                    prop_assert_eq!(next.set_pomodoros, 0);
+                }
+                if next.phase != TimerState::Focus {
+                    prop_assert!(next.extensions.is_empty());
+                }
+                state = next;
+            }
+        }
+
+        #[test]
+        fn running_phases_never_drift(rules in rules(), ticks in prop::collection::vec(1u64..=7, 1..2_000)) {
+            let mut now = start_time();
+            let mut state = transition(&MachineState::new(&rules), &rules, Event::Start { now }).state;
+            let mut phase_ends = Vec::new();
+            for seconds in ticks {
+                now += Duration::from_secs(seconds);
+                let next = transition(&state, &rules, Event::Tick { now }).state;
+                if next.phase != state.phase {
+                    phase_ends.push(state.deadline.unwrap());
+                    // Small steps never count as a missed deadline, so the next phase is
+                    // anchored to the previous deadline, not to when the tick arrived.
+                    prop_assert_eq!(next.phase_started_at, state.deadline);
+                }
+                state = next;
+            }
+            prop_assert!(state.is_running());
+            prop_assert!(phase_ends.windows(2).all(|pair| pair[0] < pair[1]));
+        }
+
+        #[test]
+        fn repeated_events_do_nothing(rules in rules(), steps in prop::collection::vec(step(), 0..40)) {
+            let mut now = start_time();
+            let mut state = MachineState::new(&rules);
+            for step in steps {
+                let event = step.event(&mut now);
+                state = transition(&state, &rules, event).state;
+            }
+            // Whatever came before, a second pause, reset or set reset has nothing left to do
+            for event in [Event::Pause { now }, Event::Reset { now }, Event::ResetSet] {
+                let once = transition(&state, &rules, event).state;
+                let twice = transition(&once, &rules, event);
+                prop_assert_eq!(&twice.effects, &Vec::new(), "{:?}", event);
+                prop_assert_eq!(&twice.state, &once);
+            }
+            let fresh = MachineState::new(&rules);
+            let reset = transition(&fresh, &rules, Event::Reset { now });
+            prop_assert!(reset.effects.is_empty());
+        }
+    }
+
+    #[test]
+    fn pausing_and_resuming_a_break_toggles_the_guard() {
+        let rules = test_rules();
+        let now = start_time();
+        let running = transition(&MachineState::new(&rules), &rules, Event::Start { now }).state;
+        let on_break = transition(&running, &rules, Event::Tick { now: now + Duration::from_secs(60) });
+        assert_eq!(on_break.state.phase, TimerState::ShortBreak);
+        assert!(on_break.effects.contains(&Effect::ShowGuard));
+        assert!(on_break.effects.contains(&Effect::Phase(PhaseChange::BreakStarted)));
+
+        let paused = transition(&on_break.state, &rules, Event::Pause { now: now + Duration::from_secs(70) });
+        assert_eq!(paused.effects, vec![Effect::HideGuard, Effect::Notify]);
+        let resumed = transition(&paused.state, &rules, Event::Start { now: now + Duration::from_secs(80) });
+        assert_eq!(resumed.effects, vec![Effect::ShowGuard, Effect::Notify]);
+        assert_eq!(resumed.state.remaining_at(now + Duration::from_secs(80)), Duration::from_secs(20));
+
+        let ended = transition(&resumed.state, &rules, Event::Tick { now: now + Duration::from_secs(100) });
+        assert_eq!(phase_effects(&ended), [PhaseChange::BreakEnded, PhaseChange::FocusStarted]);
+    }
+
+    fn test_rules() -> Rules {
+        Rules {
+            focus: Duration::from_secs(60),
+            short_break: Duration::from_secs(30),
+            long_break: Duration::from_secs(90),
+            long_break_cadence: LongBreakCadence::EveryNthPomodoro { interval: 4 },
+        }
+    }
+
+    fn phase_effects(transition: &Transition) -> Vec<PhaseChange> {
+        transition
+            .effects
+            .iter()
+            .filter_map(|effect| match effect {
+                Effect::Phase(change) => Some(*change),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn starting_a_used_up_phase_finishes_it() {
+        let rules = test_rules();
+        let now = start_time();
+        let running = transition(&MachineState::new(&rules), &rules, Event::Start { now }).state;
+        // Paused right at the deadline, before the tick that would have ended the phase
+        let paused = transition(&running, &rules, Event::Pause { now: now + rules.focus }).state;
+        assert!(paused.paused_remaining.is_zero());
+
+        let later = now + rules.focus + Duration::from_secs(300);
+        let started = transition(&paused, &rules, Event::Start { now: later });
+        assert_eq!(started.state.completed_pomodoros, 1);
+        assert_eq!(started.state.set_pomodoros, 1);
+        assert_eq!(started.state.phase, TimerState::ShortBreak);
+        assert_eq!(started.state.remaining_at(later), rules.short_break);
+        assert!(started.effects.iter().any(|effect| matches!(
+            effect,
+            Effect::Record(SessionRecord { phase: TimerState::Focus, outcome: SessionOutcome::Completed, .. })
+        )));
+        assert_eq!(phase_effects(&started), [PhaseChange::FocusEnded, PhaseChange::BreakStarted]);
+    }
+
+    #[test]
+    fn missed_focus_deadline_still_ends_focus() {
+        let rules = test_rules();
+        let now = start_time();
+        let running = transition(&MachineState::new(&rules), &rules, Event::Start { now }).state;
+
+        // Away long enough to cover the break: no break runs, but focus still ended
+        let away = now + rules.focus + rules.short_break + MISSED_DEADLINE_GRACE;
+        let back = transition(&running, &rules, Event::Tick { now: away });
+        assert_eq!(back.state.phase, TimerState::Focus);
+        assert!(!back.state.is_running());
+        assert_eq!(phase_effects(&back), [PhaseChange::FocusEnded]);
+
+        // Away for part of the break: focus ended and the rest of the break runs
+        let away = now + rules.focus + MISSED_DEADLINE_GRACE + Duration::from_secs(1);
+        let back = transition(&running, &rules, Event::Tick { now: away });
+        assert_eq!(back.state.phase, TimerState::ShortBreak);
+        assert_eq!(phase_effects(&back), [PhaseChange::FocusEnded, PhaseChange::BreakStarted]);
+    }
+}
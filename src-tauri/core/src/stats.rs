@@ -0,0 +1,355 @@
+use crate::history::{SessionOutcome, SessionRecord};
+use crate::types::TimerState;
+use chrono::{Days, NaiveDate, TimeZone, Weekday};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use ts_rs::TS;
+
+/// Breaks are judged over this many days when computing compliance for the summary.
+const COMPLIANCE_WINDOW_DAYS: u64 = 7;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+pub struct DailyStats {
+    pub date: NaiveDate,
+    #[ts(type = "number")]
+    pub focus_minutes: u64,
+    pub completed_pomodoros: u32,
+    pub breaks_taken: u32,
+    pub breaks_skipped: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+pub struct WeeklyStats {
+    /// Monday of the week.
+    pub week_start: NaiveDate,
+    #[ts(type = "number")]
+    pub focus_minutes: u64,
+    pub completed_pomodoros: u32,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+pub struct Streaks {
+    pub current_days: u32,
+    pub longest_days: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+pub struct StatsSummary {
+    pub today: DailyStats,
+    pub this_week: WeeklyStats,
+    /// Percentage of breaks over the last week that ran to completion;
+    /// `None` when there were no breaks.
+    pub break_compliance_percent: Option<f64>,
+    pub streaks: Streaks,
+}
+
+#[derive(Default)]
+struct DayTotals {
+    focus_seconds: u64,
+    completed_pomodoros: u32,
+    breaks_taken: u32,
+    breaks_skipped: u32,
+}
+
+/// Buckets records by the local calendar date on which they ended. Converting
+/// each timestamp through `tz` keeps 23- and 25-hour DST days correct.
+fn totals_by_day<Tz: TimeZone>(records: &[SessionRecord], tz: &Tz) -> BTreeMap<NaiveDate, DayTotals> {
+    let mut days: BTreeMap<NaiveDate, DayTotals> = BTreeMap::new();
+    for record in records {
+        let date = record.ended_at.with_timezone(tz).date_naive();
+        let totals = days.entry(date).or_default();
+        match (record.phase, record.outcome) {
+            (TimerState::Focus, outcome) => {
+                totals.focus_seconds += record.actual_seconds;
+                if outcome == SessionOutcome::Completed {
+                    totals.completed_pomodoros += 1;
+                }
+            }
+            (_, SessionOutcome::Completed) => totals.breaks_taken += 1,
+            (_, SessionOutcome::Skipped | SessionOutcome::Reset) => totals.breaks_skipped += 1,
+        }
+    }
+    days
+}
+
+fn week_start(date: NaiveDate) -> NaiveDate {
+    date.week(Weekday::Mon).first_day()
+}
+
+/// One entry per day from `first` to `last` inclusive, including empty days.
+pub fn daily_stats<Tz: TimeZone>(
+    records: &[SessionRecord],
+    tz: &Tz,
+    first: NaiveDate,
+    last: NaiveDate,
+) -> Vec<DailyStats> {
+    let days = totals_by_day(records, tz);
+    first
+        .iter_days()
+        .take_while(|date| *date <= last)
+        .map(|date| {
+            let totals = days.get(&date);
+            DailyStats {
+                date,
+                focus_minutes: totals.map_or(0, |t| t.focus_seconds / 60),
+                completed_pomodoros: totals.map_or(0, |t| t.completed_pomodoros),
+                breaks_taken: totals.map_or(0, |t| t.breaks_taken),
+                breaks_skipped: totals.map_or(0, |t| t.breaks_skipped),
+            }
+        })
+        .collect()
+}
+
+/// The `weeks` Monday-based weeks ending with the one containing `today`, oldest first.
+pub fn weekly_stats<Tz: TimeZone>(
+    records: &[SessionRecord],
+    tz: &Tz,
+    today: NaiveDate,
+    weeks: u32,
+) -> Vec<WeeklyStats> {
+    let days = totals_by_day(records, tz);
+    let current_week = week_start(today);
+    (0..weeks)
+        .rev()
+        .filter_map(|weeks_back| current_week.checked_sub_days(Days::new(7 * u64::from(weeks_back))))
+        .map(|week_start| {
+            let week_end = week_start + Days::new(7);
+            let (focus_seconds, completed_pomodoros) = days
+                .range(week_start..week_end)
+                .fold((0, 0), |(seconds, pomodoros), (_, t)| {
+                    (seconds + t.focus_seconds, pomodoros + t.completed_pomodoros)
+                });
+            WeeklyStats {
+                week_start,
+                focus_minutes: focus_seconds / 60,
+                completed_pomodoros,
+            }
+        })
+        .collect()
+}
+
+/// Percentage of breaks ending between `first` and `last` that were not cut short.
+pub fn break_compliance<Tz: TimeZone>(
+    records: &[SessionRecord],
+    tz: &Tz,
+    first: NaiveDate,
+    last: NaiveDate,
+) -> Option<f64> {
+    let days = totals_by_day(records, tz);
+    let (taken, skipped) = days
+        .range(first..=last)
+        .fold((0u32, 0u32), |(taken, skipped), (_, t)| {
+            (taken + t.breaks_taken, skipped + t.breaks_skipped)
+        });
+    let total = taken + skipped;
+    (total > 0).then(|| f64::from(taken) * 100.0 / f64::from(total))
+}
+
+/// A streak is a run of consecutive days with at least one completed pomodoro.
+/// The current streak is still alive if today has no pomodoro yet but yesterday did.
+pub fn streaks<Tz: TimeZone>(records: &[SessionRecord], tz: &Tz, today: NaiveDate) -> Streaks {
+    let active_days: Vec<NaiveDate> = totals_by_day(records, tz)
+        .into_iter()
+        .filter(|(date, t)| t.completed_pomodoros > 0 && *date <= today)
+        .map(|(date, _)| date)
+        .collect();
+
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for &date in &active_days {
+        run = match previous {
+            Some(previous) if previous.succ_opt() == Some(date) => run + 1,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        previous = Some(date);
+    }
+
+    let yesterday = today.pred_opt();
+    let current = match previous {
+        Some(last) if last == today || Some(last) == yesterday => run,
+        _ => 0,
+    };
+
+    Streaks {
+        current_days: current,
+        longest_days: longest,
+    }
+}
+
+pub fn summary<Tz: TimeZone>(records: &[SessionRecord], tz: &Tz, today: NaiveDate) -> StatsSummary {
+    let compliance_start = today
+        .checked_sub_days(Days::new(COMPLIANCE_WINDOW_DAYS - 1))
+        .unwrap_or(today);
+    StatsSummary {
+        today: daily_stats(records, tz, today, today).pop().unwrap_or_default(),
+        this_week: weekly_stats(records, tz, today, 1)
+            .pop()
+            .unwrap_or(WeeklyStats {
+                week_start: week_start(today),
+                focus_minutes: 0,
+                completed_pomodoros: 0,
+            }),
+        break_compliance_percent: break_compliance(records, tz, compliance_start, today),
+        streaks: streaks(records, tz, today),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Duration, Utc};
+    use chrono_tz::Europe::Berlin;
+
+    fn record(phase: TimerState, outcome: SessionOutcome, ended_at: &str, actual_seconds: u64) -> SessionRecord {
+        let ended_at: DateTime<Utc> = ended_at.parse().unwrap();
+        SessionRecord {
+            phase,
+            outcome,
+            planned_seconds: actual_seconds,
+            actual_seconds,
+            started_at: ended_at - Duration::seconds(actual_seconds as i64),
+            ended_at,
+            extensions: Vec::new(),
+        }
+    }
+
+    fn focus(ended_at: &str) -> SessionRecord {
+        record(TimerState::Focus, SessionOutcome::Completed, ended_at, 25 * 60)
+    }
+
+    fn date(raw: &str) -> NaiveDate {
+        raw.parse().unwrap()
+    }
+
+    #[test]
+    fn buckets_by_local_date_across_dst_change() {
+        // Berlin leaves summer time on 2024-10-27: 22:30Z is 23:30 CET that day,
+        // although it would already be the 28th under the summer offset.
+        let records = vec![focus("2024-10-27T22:30:00Z"), focus("2024-10-27T23:30:00Z")];
+        let days = daily_stats(&records, &Berlin, date("2024-10-27"), date("2024-10-28"));
+        assert_eq!(days[0].completed_pomodoros, 1);
+        assert_eq!(days[0].focus_minutes, 25);
+        assert_eq!(days[1].completed_pomodoros, 1);
+    }
+
+    #[test]
+    fn daily_stats_include_empty_days_and_partial_focus() {
+        let records = vec![
+            focus("2024-05-06T08:00:00Z"),
+            record(TimerState::Focus, SessionOutcome::Reset, "2024-05-06T09:00:00Z", 10 * 60),
+            record(TimerState::ShortBreak, SessionOutcome::Completed, "2024-05-06T09:10:00Z", 300),
+            record(TimerState::LongBreak, SessionOutcome::Skipped, "2024-05-06T10:00:00Z", 60),
+        ];
+        let days = daily_stats(&records, &Berlin, date("2024-05-05"), date("2024-05-07"));
+        assert_eq!(days.len(), 3);
+        assert_eq!(days[0], DailyStats { date: date("2024-05-05"), ..DailyStats::default() });
+        assert_eq!(days[1], DailyStats {
+            date: date("2024-05-06"),
+            focus_minutes: 35,
+            completed_pomodoros: 1,
+            breaks_taken: 1,
+            breaks_skipped: 1,
+        });
+    }
+
+    #[test]
+    fn weekly_stats_start_on_monday() {
+        let records = vec![
+            focus("2024-05-05T12:00:00Z"), // Sunday
+            focus("2024-05-06T12:00:00Z"), // Monday
+            focus("2024-05-12T12:00:00Z"), // Sunday
+        ];
+        let weeks = weekly_stats(&records, &Berlin, date("2024-05-08"), 2);
+        assert_eq!(weeks.len(), 2);
+        assert_eq!(weeks[0].week_start, date("2024-04-29"));
+        assert_eq!(weeks[0].completed_pomodoros, 1);
+        assert_eq!(weeks[1].week_start, date("2024-05-06"));
+        assert_eq!(weeks[1].completed_pomodoros, 2);
+        assert_eq!(weeks[1].focus_minutes, 50);
+    }
+
+    #[test]
+    fn break_compliance_counts_skips_and_resets() {
+        let records = vec![
+            record(TimerState::ShortBreak, SessionOutcome::Completed, "2024-05-06T09:00:00Z", 300),
+            record(TimerState::ShortBreak, SessionOutcome::Completed, "2024-05-06T10:00:00Z", 300),
+            record(TimerState::ShortBreak, SessionOutcome::Completed, "2024-05-06T11:00:00Z", 300),
+            record(TimerState::LongBreak, SessionOutcome::Reset, "2024-05-06T12:00:00Z", 60),
+        ];
+        let today = date("2024-05-06");
+        assert_eq!(break_compliance(&records, &Berlin, today, today), Some(75.0));
+        assert_eq!(break_compliance(&[], &Berlin, today, today), None);
+    }
+
+    #[test]
+    fn streaks_track_consecutive_active_days() {
+        let records = vec![
+            focus("2024-05-01T12:00:00Z"),
+            focus("2024-05-02T12:00:00Z"),
+            focus("2024-05-03T12:00:00Z"),
+            focus("2024-05-06T12:00:00Z"),
+            focus("2024-05-07T12:00:00Z"),
+            record(TimerState::Focus, SessionOutcome::Reset, "2024-05-08T12:00:00Z", 600),
+        ];
+        assert_eq!(streaks(&records, &Berlin, date("2024-05-08")), Streaks { current_days: 2, longest_days: 3 });
+        assert_eq!(streaks(&records, &Berlin, date("2024-05-09")), Streaks { current_days: 0, longest_days: 3 });
+    }
+
+    #[test]
+    fn streaks_break_on_a_missed_local_day() {
+        // 22:30Z on the 3rd is already the 4th in Berlin, so the 3rd has no pomodoro
+        let records = vec![
+            focus("2024-05-02T12:00:00Z"),
+            focus("2024-05-03T22:30:00Z"),
+            focus("2024-05-05T12:00:00Z"),
+            record(TimerState::ShortBreak, SessionOutcome::Completed, "2024-05-06T12:00:00Z", 300),
+        ];
+        assert_eq!(streaks(&records, &Berlin, date("2024-05-05")), Streaks { current_days: 2, longest_days: 2 });
+        // A break alone does not count; the streak is still alive because yesterday did
+        assert_eq!(streaks(&records, &Berlin, date("2024-05-06")), Streaks { current_days: 2, longest_days: 2 });
+        assert_eq!(streaks(&records, &Berlin, date("2024-05-07")), Streaks { current_days: 0, longest_days: 2 });
+        // Records after `today` are ignored
+        assert_eq!(streaks(&records, &Berlin, date("2024-05-03")), Streaks { current_days: 1, longest_days: 1 });
+    }
+
+    #[test]
+    fn summary_judges_breaks_over_the_last_seven_local_days() {
+        let today = date("2024-05-08");
+        let records = vec![
+            // 23:59 on the 1st in Berlin: a day before the window
+            record(TimerState::ShortBreak, SessionOutcome::Skipped, "2024-05-01T21:59:00Z", 60),
+            // 00:00 on the 2nd: the first minute of the window
+            record(TimerState::ShortBreak, SessionOutcome::Completed, "2024-05-01T22:00:00Z", 300),
+            record(TimerState::LongBreak, SessionOutcome::Completed, "2024-05-06T12:00:00Z", 900),
+            record(TimerState::ShortBreak, SessionOutcome::Reset, "2024-05-07T12:00:00Z", 30),
+            // 23:59 today: the last minute of the window
+            record(TimerState::ShortBreak, SessionOutcome::Skipped, "2024-05-08T21:59:00Z", 60),
+            // 00:00 tomorrow: after the window
+            record(TimerState::ShortBreak, SessionOutcome::Skipped, "2024-05-08T22:00:00Z", 60),
+            focus("2024-05-06T08:00:00Z"),
+            focus("2024-05-07T08:00:00Z"),
+            focus("2024-05-08T08:00:00Z"),
+            focus("2024-05-08T09:00:00Z"),
+        ];
+        let summary = summary(&records, &Berlin, today);
+        // Two breaks taken of the four owed in the window
+        assert_eq!(summary.break_compliance_percent, Some(50.0));
+        assert_eq!(summary.today.completed_pomodoros, 2);
+        assert_eq!(summary.today.breaks_skipped, 1);
+        assert_eq!(summary.this_week.week_start, date("2024-05-06"));
+        assert_eq!(summary.this_week.completed_pomodoros, 4);
+        assert_eq!(summary.streaks, Streaks { current_days: 3, longest_days: 3 });
+    }
+
+    #[test]
+    fn summary_of_an_empty_history() {
+        let summary = summary(&[], &Berlin, date("2024-05-08"));
+        assert_eq!(summary.today, DailyStats { date: date("2024-05-08"), ..DailyStats::default() });
+        assert_eq!(summary.this_week.week_start, date("2024-05-06"));
+        assert_eq!(summary.this_week.focus_minutes, 0);
+        assert_eq!(summary.break_compliance_percent, None);
+        assert_eq!(summary.streaks, Streaks::default());
+    }
+}
@@ -0,0 +1,1258 @@
+use crate::clock::Clock;
+use crate::events::{EngineEvent, EventSink};
+use crate::history::{HistoryRecorder, SessionRecord};
+use crate::session_store::{from_unix_ms, to_unix_ms, EngineSnapshot, SnapshotStore};
+use crate::settings::{Enforcement, ExtensionPolicy, LongBreakCadence, Profile};
+use crate::skip_policy::{self, SkipContext, SkipDenied, SkipPolicy, SkipStatus};
+use crate::state_machine::{
+    self, Effect, Event, MachineState, PhaseLengths, Rules, Transition, MISSED_DEADLINE_GRACE,
+};
+use crate::types::{
+    AllowedControls, BreakWarning, ExtensionOffer, PhaseChange, PhaseStatus, PhaseTransition, SetPosition, TimerState,
+    TimerUpdate,
+};
+use chrono::{DateTime, Local, NaiveDate};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::watch;
+
+/// How often a running engine re-saves its snapshot between transitions.
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(30);
+/// How long before the end of a focus phase the extension prompt is offered.
+const EXTENSION_OFFER_LEAD: Duration = Duration::from_secs(60);
+
+/// Runs the timer state machine against a clock and carries out its effects:
+/// guard and update events, history records, snapshots and the phase channel.
+/// Policies (skip, extension, lockdown) are enforced here before an event is
+/// handed to [`state_machine::transition`].
+pub struct TimerEngine {
+    machine: MachineState,
+    rules: Rules,
+    /// Replaces the phase lengths of every profile; see `override_phase_lengths`.
+    phase_lengths: Option<PhaseLengths>,
+    enforcement: Enforcement,
+    extension_policy: ExtensionPolicy,
+    skip_policy: SkipPolicy,
+    /// Local day `skips_on_day` counts skips for.
+    skip_day: Option<NaiveDate>,
+    skips_on_day: u32,
+    /// Whether the host locks the screen during breaks; see `break_locked`.
+    break_lockdown: bool,
+    extension_offered: bool,
+    /// Seconds-before-break thresholds at which a warning is emitted.
+    break_warnings: Vec<u32>,
+    /// Thresholds already warned about in the current phase.
+    warnings_sent: Vec<u32>,
+    last_emitted_seconds: Option<u32>,
+    clock: Arc<dyn Clock>,
+    sink: Arc<dyn EventSink>,
+    phase_tx: watch::Sender<PhaseStatus>,
+    snapshot_store: Option<Arc<dyn SnapshotStore>>,
+    last_snapshot_at: Option<SystemTime>,
+    history: Option<Arc<dyn HistoryRecorder>>,
+}
+
+impl TimerEngine {
+    pub fn new(profile: &Profile, clock: Arc<dyn Clock>, sink: Arc<dyn EventSink>) -> Self {
+        let rules = Rules::from_profile(profile);
+        Self {
+            machine: MachineState::new(&rules),
+            rules,
+            phase_lengths: None,
+            enforcement: profile.enforcement,
+            extension_policy: profile.extension,
+            skip_policy: profile.skip.clone(),
+            skip_day: None,
+            skips_on_day: 0,
+            break_lockdown: false,
+            extension_offered: false,
+            break_warnings: Vec::new(),
+            warnings_sent: Vec::new(),
+            last_emitted_seconds: None,
+            clock,
+            sink,
+            phase_tx: watch::Sender::new(PhaseStatus {
+                state: TimerState::Focus,
+                is_running: false,
+                break_locked: false,
+            }),
+            snapshot_store: None,
+            last_snapshot_at: None,
+            history: None,
+        }
+    }
+
+    /// Tells the engine whether breaks are shown in kiosk lockdown, which
+    /// makes it refuse pausing breaks and treat resets as skips.
+    pub fn set_break_lockdown(&mut self, enabled: bool) {
+        self.break_lockdown = enabled;
+        self.publish_phase();
+    }
+
+    /// Runs the current and every later profile with `lengths` instead of its
+    /// own phase lengths, keeping its long-break cadence. An untouched idle
+    /// phase is resized at once.
+    pub fn override_phase_lengths(&mut self, lengths: PhaseLengths) {
+        self.phase_lengths = Some(lengths);
+        self.rules = self.rules.with_lengths(lengths);
+        self.resize_untouched_phase();
+    }
+
+    /// Receives the latest `PhaseStatus` whenever it changes, so observers
+    /// such as the guard window can react without polling the engine.
+    pub fn subscribe_phase(&self) -> watch::Receiver<PhaseStatus> {
+        self.phase_tx.subscribe()
+    }
+
+    fn publish_phase(&self) {
+        let status = PhaseStatus {
+            state: self.machine.phase,
+            is_running: self.is_running(),
+            break_locked: self.break_locked(),
+        };
+        self.phase_tx.send_if_modified(|current| {
+            let changed = *current != status;
+            *current = status;
+            changed
+        });
+    }
+
+    /// Emits a warning as the focus phase crosses each of `thresholds` seconds
+    /// before the break.
+    pub fn set_break_warnings(&mut self, thresholds: &[u32]) {
+        self.break_warnings = thresholds.to_vec();
+        self.break_warnings.sort_unstable_by(|a, b| b.cmp(a));
+        self.break_warnings.dedup();
+        self.warnings_sent.retain(|sent| thresholds.contains(sent));
+    }
+
+    /// Records every finished or aborted phase to `history`.
+    pub fn set_history_recorder(&mut self, history: Arc<dyn HistoryRecorder>) {
+        self.history = Some(history);
+    }
+
+    /// Persists the engine state to `store` on every transition and periodically while running.
+    pub fn set_snapshot_store(&mut self, store: Arc<dyn SnapshotStore>) {
+        self.snapshot_store = Some(store);
+    }
+
+    pub fn snapshot(&self) -> EngineSnapshot {
+        let machine = &self.machine;
+        EngineSnapshot {
+            state: machine.phase,
+            completed_pomodoros: machine.completed_pomodoros,
+            set_pomodoros: machine.set_pomodoros,
+            set_focus_seconds: machine.set_focus_seconds,
+            phase_length_ms: machine.phase_length.as_millis() as u64,
+            deadline_unix_ms: machine.deadline.map(to_unix_ms),
+            paused_remaining_ms: machine.paused_remaining.as_millis() as u64,
+            phase_started_unix_ms: machine.phase_started_at.map(to_unix_ms),
+            extensions: machine.extensions.clone(),
+            skip_day: self.skip_day,
+            skips_on_day: self.skips_on_day,
+            saved_at_unix_ms: to_unix_ms(self.clock.now()),
+        }
+    }
+
+    /// Resumes from a snapshot taken before the app last exited. A break that was
+    /// still running re-shows the guard, so quitting is not a way to dodge it; a
+    /// deadline that passed while the app was closed is handled by the next tick
+    /// like any other missed deadline.
+    pub fn restore(&mut self, snapshot: &EngineSnapshot) -> Result<(), Box<dyn std::error::Error>> {
+        let phase_length = Duration::from_millis(snapshot.phase_length_ms);
+        self.machine = MachineState {
+            phase: snapshot.state,
+            completed_pomodoros: snapshot.completed_pomodoros,
+            set_pomodoros: snapshot.set_pomodoros,
+            set_focus_seconds: snapshot.set_focus_seconds,
+            phase_length,
+            deadline: snapshot.deadline_unix_ms.map(from_unix_ms),
+            paused_remaining: Duration::from_millis(snapshot.paused_remaining_ms).min(phase_length),
+            phase_started_at: snapshot.phase_started_unix_ms.map(from_unix_ms),
+            extensions: snapshot.extensions.clone(),
+        };
+        self.skip_day = snapshot.skip_day;
+        self.skips_on_day = snapshot.skips_on_day;
+        println!("[TimerEngine] Restored session: state={:?}, remaining={}, running={}",
+            self.machine.phase, self.get_remaining_seconds(), self.is_running());
+
+        let break_still_running = self
+            .machine
+            .deadline
+            .is_some_and(|deadline| self.machine.phase.is_break() && deadline > self.clock.now());
+        if break_still_running {
+            self.sink.emit(EngineEvent::ShowGuard)?;
+        }
+        self.emit_update()?;
+        Ok(())
+    }
+
+    /// Switches the live engine to `profile`. A phase that is already under way
+    /// keeps its remaining time and deadline; the new durations take effect from
+    /// the next phase and the new cadence is judged against the current set when
+    /// that focus phase ends. An idle phase that has not been started yet is
+    /// resized immediately. Enforcement changes apply at once.
+    pub fn apply_profile(&mut self, profile: &Profile) -> Result<(), Box<dyn std::error::Error>> {
+        let rules = Rules::from_profile(profile);
+        self.rules = self.phase_lengths.map_or(rules, |lengths| rules.with_lengths(lengths));
+        self.enforcement = profile.enforcement;
+        self.extension_policy = profile.extension;
+        self.skip_policy = profile.skip.clone();
+        self.resize_untouched_phase();
+
+        println!("Profile applied: {:?}", profile);
+        self.persist();
+        self.emit_update()?;
+        Ok(())
+    }
+
+    fn resize_untouched_phase(&mut self) {
+        if self.machine.is_untouched() {
+            let phase = self.machine.phase;
+            self.machine.enter_phase(&self.rules, phase, None);
+        }
+    }
+
+    pub fn start(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        println!("Timer start called");
+        self.apply(Event::Start { now: self.clock.now() })
+    }
+
+    pub fn pause(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        println!("Timer pause called");
+        if self.break_locked() {
+            return Err("Breaks cannot be paused while the break guard is enforced".into());
+        }
+        self.apply(Event::Pause { now: self.clock.now() })
+    }
+
+    /// Resets to an idle focus phase and starts a new set. During an enforced
+    /// break this counts as a skip, so it is subject to the skip policy and
+    /// `confirmation` is checked against its phrase.
+    pub fn reset(&mut self, confirmation: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+        println!("Timer reset called");
+        let now = self.clock.now();
+        if self.break_locked() {
+            skip_policy::check(&self.skip_policy, &self.skip_context(), confirmation)?;
+            self.count_skip(now);
+        }
+        self.apply(Event::Reset { now })
+    }
+
+    /// Adds time to the current focus phase, by default one extension of the
+    /// profile's configured length. Refused outside focus and once the cycle's
+    /// extension count or total is used up.
+    pub fn extend_phase(&mut self, minutes: Option<u32>) -> Result<(), Box<dyn std::error::Error>> {
+        println!("Timer extend called");
+        if self.machine.phase != TimerState::Focus {
+            return Err("Only focus phases can be extended".into());
+        }
+
+        let policy = self.extension_policy;
+        let minutes = minutes.unwrap_or(policy.minutes);
+        if minutes == 0 || minutes > policy.minutes {
+            return Err(format!("An extension must be between 1 and {} minutes", policy.minutes).into());
+        }
+        let extensions = &self.machine.extensions;
+        if extensions.len() as u32 >= policy.max_per_cycle {
+            return Err(format!("No extensions left this cycle (limit {})", policy.max_per_cycle).into());
+        }
+        let extended_seconds: u64 = extensions.iter().map(|e| e.seconds).sum();
+        let seconds = u64::from(minutes) * 60;
+        if extended_seconds + seconds > u64::from(policy.max_total_minutes) * 60 {
+            return Err(format!(
+                "Extending by {} minutes would exceed the {}-minute limit this cycle",
+                minutes, policy.max_total_minutes
+            )
+            .into());
+        }
+
+        self.apply(Event::Extend { now: self.clock.now(), seconds })?;
+        println!("[TimerEngine] Focus extended by {} minutes ({} this cycle)",
+            minutes, self.machine.extensions.len());
+        Ok(())
+    }
+
+    /// Starts a new long-break set without touching the current phase or the
+    /// lifetime pomodoro count.
+    pub fn reset_set(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        println!("Long-break set reset called");
+        self.apply(Event::ResetSet)
+    }
+
+    /// Ends the current break early if the profile's skip policy allows it.
+    pub fn skip_break(&mut self, confirmation: Option<&str>) -> Result<(), SkipDenied> {
+        skip_policy::check(&self.skip_policy, &self.skip_context(), confirmation)?;
+
+        let now = self.clock.now();
+        self.count_skip(now);
+        // The skip itself has happened; failing to tell front ends about it is not a denial.
+        if let Err(e) = self.apply(Event::Skip { now }) {
+            eprintln!("Failed to emit events after skip: {}", e);
+        }
+        Ok(())
+    }
+
+    fn count_skip(&mut self, now: SystemTime) {
+        let today = local_date(now);
+        if self.skip_day != Some(today) {
+            self.skip_day = Some(today);
+            self.skips_on_day = 0;
+        }
+        self.skips_on_day += 1;
+        println!("[TimerEngine] Break skipped ({} today)", self.skips_on_day);
+    }
+
+    pub fn skip_status(&self) -> SkipStatus {
+        let skips_today = self.skips_today();
+        SkipStatus {
+            denied: skip_policy::check_preconditions(&self.skip_policy, &self.skip_context()).err(),
+            skips_left_today: self.skip_policy.daily_quota.map(|quota| quota.saturating_sub(skips_today)),
+            confirmation_phrase: self.skip_policy.confirmation_phrase.clone(),
+        }
+    }
+
+    fn skips_today(&self) -> u32 {
+        if self.skip_day == Some(local_date(self.clock.now())) {
+            self.skips_on_day
+        } else {
+            0
+        }
+    }
+
+    fn skip_context(&self) -> SkipContext {
+        let remaining = self.machine.remaining_at(self.clock.now());
+        SkipContext {
+            in_break: self.machine.phase.is_break(),
+            skipping_disabled: self.enforcement == Enforcement::Strict,
+            elapsed: self.machine.phase_length.saturating_sub(remaining),
+            phase_length: self.machine.phase_length,
+            skips_today: self.skips_today(),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.machine.is_running()
+    }
+
+    pub fn enforcement(&self) -> Enforcement {
+        self.enforcement
+    }
+
+    /// True during a break that the guard enforces; such a break can only be
+    /// left early through the skip policy.
+    pub fn break_locked(&self) -> bool {
+        self.break_lockdown && self.enforcement != Enforcement::Gentle && self.machine.phase.is_break()
+    }
+
+    pub fn allowed_controls(&self) -> AllowedControls {
+        let can_skip = self.skip_status().denied.is_none();
+        AllowedControls {
+            start: !self.is_running(),
+            pause: self.is_running() && !self.break_locked(),
+            reset: !self.break_locked() || can_skip,
+            skip: can_skip,
+            extend: self.machine.phase == TimerState::Focus && self.extensions_left() > 0,
+        }
+    }
+
+    pub fn get_state(&self) -> TimerState {
+        self.machine.phase
+    }
+
+    pub fn get_remaining_seconds(&self) -> u32 {
+        let remaining = self.machine.remaining_at(self.clock.now());
+        // Round up so a fresh phase reads 25:00 rather than 24:59.
+        remaining.as_millis().div_ceil(1000) as u32
+    }
+
+    pub fn get_completed_pomodoros(&self) -> u32 {
+        self.machine.completed_pomodoros
+    }
+
+    pub fn get_update(&self) -> TimerUpdate {
+        let machine = &self.machine;
+        let remaining = machine.remaining_at(self.clock.now());
+        let progress = if machine.phase_length.is_zero() {
+            1.0
+        } else {
+            1.0 - remaining.as_secs_f64() / machine.phase_length.as_secs_f64()
+        };
+        let next_state = match machine.phase {
+            TimerState::Focus => machine.upcoming_break(&self.rules),
+            TimerState::ShortBreak | TimerState::LongBreak => TimerState::Focus,
+        };
+        TimerUpdate {
+            state: machine.phase,
+            remaining_seconds: self.get_remaining_seconds(),
+            total_seconds: machine.phase_length.as_secs() as u32,
+            progress: progress.clamp(0.0, 1.0),
+            ends_at: machine.deadline.map(Into::into),
+            next_state,
+            set_position: self.set_position(),
+            completed_pomodoros: machine.completed_pomodoros,
+            is_running: self.is_running(),
+            controls: self.allowed_controls(),
+        }
+    }
+
+    pub async fn tick(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(deadline) = self.machine.deadline else {
+            return Ok(());
+        };
+
+        let now = self.clock.now();
+        if let Ok(overdue) = now.duration_since(deadline) {
+            if overdue > MISSED_DEADLINE_GRACE {
+                println!("[TimerEngine] {:?} deadline missed by {}s, treating the gap as rest",
+                    self.machine.phase, overdue.as_secs());
+            }
+            return self.apply(Event::Tick { now });
+        }
+
+        let snapshot_due = self
+            .last_snapshot_at
+            .is_none_or(|at| now.duration_since(at).unwrap_or(Duration::ZERO) >= SNAPSHOT_INTERVAL);
+        if snapshot_due {
+            self.persist();
+        }
+
+        let remaining = deadline.duration_since(now).unwrap_or(Duration::ZERO);
+        self.warn_if_due(remaining)?;
+        self.offer_extension_if_due(remaining)?;
+
+        // Only emit when the displayed second actually changes.
+        let remaining_seconds = self.get_remaining_seconds();
+        if self.last_emitted_seconds != Some(remaining_seconds) {
+            println!("Timer tick: state={:?}, remaining={}, running={}",
+                self.machine.phase, remaining_seconds, self.is_running());
+            self.emit_update()?;
+        }
+        Ok(())
+    }
+
+    fn set_position(&self) -> SetPosition {
+        let (pomodoros_per_set, focus_minutes_per_set) = match self.rules.long_break_cadence {
+            LongBreakCadence::EveryNthPomodoro { interval } => (Some(interval.max(1)), None),
+            LongBreakCadence::FocusMinutes { minutes } => (None, Some(minutes)),
+        };
+        SetPosition {
+            pomodoros: self.machine.set_pomodoros,
+            pomodoros_per_set,
+            focus_minutes: self.machine.set_focus_seconds / 60,
+            focus_minutes_per_set,
+        }
+    }
+
+    /// Feeds `event` through the state machine, then saves, publishes and
+    /// carries out the resulting effects in order.
+    fn apply(&mut self, event: Event) -> Result<(), Box<dyn std::error::Error>> {
+        let Transition { state, effects } = state_machine::transition(&self.machine, &self.rules, event);
+        if effects.is_empty() {
+            return Ok(());
+        }
+
+        let old = &self.machine;
+        let old_phase = old.phase;
+        let new_phase = state.phase != old.phase
+            || state.phase_started_at != old.phase_started_at
+            || state.phase_length != old.phase_length;
+        if new_phase {
+            // A new or lengthened phase gets its own warnings and extension offer.
+            self.extension_offered = false;
+            self.warnings_sent.clear();
+        }
+        if state.phase != old.phase {
+            println!("[TimerEngine] {:?} -> {:?}", old.phase, state.phase);
+        }
+        self.machine = state;
+        self.persist();
+        self.publish_phase();
+
+        for effect in effects {
+            match effect {
+                Effect::ShowGuard => self.sink.emit(EngineEvent::ShowGuard)?,
+                Effect::HideGuard => self.sink.emit(EngineEvent::HideGuard)?,
+                Effect::Record(record) => self.record_phase(record),
+                Effect::Phase(change) => self.emit_transition(old_phase, change)?,
+                Effect::Notify => self.emit_update()?,
+            }
+        }
+        Ok(())
+    }
+
+    fn extensions_left(&self) -> u32 {
+        let policy = self.extension_policy;
+        let extensions = &self.machine.extensions;
+        let extended_seconds: u64 = extensions.iter().map(|e| e.seconds).sum();
+        let by_count = policy.max_per_cycle.saturating_sub(extensions.len() as u32);
+        let by_total = (u64::from(policy.max_total_minutes) * 60).saturating_sub(extended_seconds)
+            / (u64::from(policy.minutes.max(1)) * 60);
+        by_count.min(by_total as u32)
+    }
+
+    /// Offers a focus extension once per phase, shortly before the break starts.
+    fn offer_extension_if_due(&mut self, remaining: Duration) -> Result<(), Box<dyn std::error::Error>> {
+        if self.machine.phase != TimerState::Focus || self.extension_offered || remaining > EXTENSION_OFFER_LEAD {
+            return Ok(());
+        }
+        self.extension_offered = true;
+        let extensions_left = self.extensions_left();
+        if extensions_left == 0 {
+            return Ok(());
+        }
+        self.sink.emit(EngineEvent::ExtensionOffer(ExtensionOffer {
+            remaining_seconds: remaining.as_millis().div_ceil(1000) as u32,
+            extension_minutes: self.extension_policy.minutes,
+            extensions_left,
+        }))?;
+        Ok(())
+    }
+
+    fn record_phase(&self, record: SessionRecord) {
+        if let Some(history) = &self.history {
+            if let Err(e) = history.record(&record) {
+                eprintln!("Failed to record session history: {}", e);
+            }
+        }
+    }
+
+    /// Warns once per threshold; if several were crossed at once (e.g. after a
+    /// sleep) only the closest one is announced.
+    fn warn_if_due(&mut self, remaining: Duration) -> Result<(), Box<dyn std::error::Error>> {
+        if self.machine.phase != TimerState::Focus {
+            return Ok(());
+        }
+        let remaining_seconds = remaining.as_millis().div_ceil(1000) as u32;
+        let crossed: Vec<u32> = self
+            .break_warnings
+            .iter()
+            .copied()
+            .filter(|threshold| remaining_seconds <= *threshold && !self.warnings_sent.contains(threshold))
+            .collect();
+        let Some(&closest) = crossed.last() else {
+            return Ok(());
+        };
+        self.warnings_sent.extend(crossed);
+        println!("[TimerEngine] Break warning: {}s left", remaining_seconds);
+        self.sink.emit(EngineEvent::BreakWarning(BreakWarning {
+            seconds_left: closest,
+            next_state: self.machine.upcoming_break(&self.rules),
+        }))?;
+        Ok(())
+    }
+
+    fn persist(&mut self) {
+        let Some(store) = &self.snapshot_store else {
+            return;
+        };
+        if let Err(e) = store.save(&self.snapshot()) {
+            eprintln!("Failed to save session snapshot: {}", e);
+        }
+        self.last_snapshot_at = Some(self.clock.now());
+    }
+
+    fn emit_update(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.publish_phase();
+        let update = self.get_update();
+        println!("Emitting update: state={:?}, remaining={}, running={}",
+            update.state, update.remaining_seconds, update.is_running);
+        self.last_emitted_seconds = Some(update.remaining_seconds);
+        self.sink.emit(EngineEvent::TimerUpdate(update))?;
+        Ok(())
+    }
+
+    fn emit_transition(&self, from: TimerState, change: PhaseChange) -> Result<(), Box<dyn std::error::Error>> {
+        let transition = PhaseTransition {
+            change,
+            from,
+            to: self.machine.phase,
+            at: self.clock.now().into(),
+            update: self.get_update(),
+        };
+        self.sink.emit(EngineEvent::PhaseTransition(transition))?;
+        Ok(())
+    }
+}
+
+fn local_date(time: SystemTime) -> NaiveDate {
+    DateTime::<Local>::from(time).date_naive()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::SessionOutcome;
+    use crate::settings::LongBreakCadence;
+    use std::sync::Mutex as StdMutex;
+
+    const FOCUS: Duration = Duration::from_secs(25 * 60);
+    const SHORT_BREAK: Duration = Duration::from_secs(5 * 60);
+    const LONG_BREAK: Duration = Duration::from_secs(25 * 60);
+
+    #[derive(Clone)]
+    struct FakeClock {
+        now: Arc<StdMutex<SystemTime>>,
+    }
+
+    impl FakeClock {
+        fn new() -> Self {
+            Self {
+                now: Arc::new(StdMutex::new(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000))),
+            }
+        }
+
+        fn advance(&self, by: Duration) {
+            *self.now.lock().unwrap() += by;
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> SystemTime {
+            *self.now.lock().unwrap()
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordingSink {
+        events: StdMutex<Vec<EngineEvent>>,
+    }
+
+    impl RecordingSink {
+        fn take(&self) -> Vec<EngineEvent> {
+            std::mem::take(&mut *self.events.lock().unwrap())
+        }
+
+        fn guard_events(&self) -> Vec<EngineEvent> {
+            self.take()
+                .into_iter()
+                .filter(|event| matches!(event, EngineEvent::ShowGuard | EngineEvent::HideGuard))
+                .collect()
+        }
+
+        fn transitions(&self) -> Vec<PhaseTransition> {
+            self.take()
+                .into_iter()
+                .filter_map(|event| match event {
+                    EngineEvent::PhaseTransition(transition) => Some(transition),
+                    _ => None,
+                })
+                .collect()
+        }
+    }
+
+    impl EventSink for RecordingSink {
+        fn emit(&self, event: EngineEvent) -> Result<(), Box<dyn std::error::Error>> {
+            self.events.lock().unwrap().push(event);
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordingHistory {
+        records: StdMutex<Vec<SessionRecord>>,
+    }
+
+    impl HistoryRecorder for RecordingHistory {
+        fn record(&self, record: &SessionRecord) -> Result<(), String> {
+            self.records.lock().unwrap().push(record.clone());
+            Ok(())
+        }
+    }
+
+    fn new_engine() -> (TimerEngine, FakeClock, Arc<RecordingSink>) {
+        let clock = FakeClock::new();
+        let sink = Arc::new(RecordingSink::default());
+        let engine = TimerEngine::new(&Profile::default(), Arc::new(clock.clone()), sink.clone());
+        (engine, clock, sink)
+    }
+
+    /// Advances the clock through the rest of the current phase and ticks once.
+    async fn finish_phase(engine: &mut TimerEngine, clock: &FakeClock) {
+        clock.advance(Duration::from_secs(engine.get_remaining_seconds().into()));
+        engine.tick().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn starts_idle_in_full_focus_phase() {
+        let (engine, _, _) = new_engine();
+        assert_eq!(engine.get_state(), TimerState::Focus);
+        assert_eq!(engine.get_remaining_seconds(), 25 * 60);
+        assert!(!engine.is_running());
+    }
+
+    #[tokio::test]
+    async fn update_describes_phase_totals_and_set_position() {
+        let (mut engine, clock, _) = new_engine();
+        let idle = engine.get_update();
+        assert_eq!(idle.total_seconds, 25 * 60);
+        assert_eq!(idle.progress, 0.0);
+        assert_eq!(idle.ends_at, None);
+        assert_eq!(idle.next_state, TimerState::ShortBreak);
+        assert_eq!(idle.countdown(), "25:00");
+
+        engine.start().unwrap();
+        clock.advance(FOCUS / 5);
+        let update = engine.get_update();
+        assert!((update.progress - 0.2).abs() < 1e-9);
+        assert_eq!(update.countdown(), "20:00");
+        assert_eq!(update.ends_at, Some((clock.now() + FOCUS * 4 / 5).into()));
+
+        finish_phase(&mut engine, &clock).await;
+        finish_phase(&mut engine, &clock).await;
+        finish_phase(&mut engine, &clock).await;
+        let update = engine.get_update();
+        assert_eq!(update.state, TimerState::ShortBreak);
+        assert_eq!(update.total_seconds, 5 * 60);
+        assert_eq!(update.next_state, TimerState::Focus);
+        assert_eq!(update.set_position, SetPosition {
+            pomodoros: 2,
+            pomodoros_per_set: Some(3),
+            focus_minutes: 50,
+            focus_minutes_per_set: None,
+        });
+    }
+
+    #[tokio::test]
+    async fn idle_engine_does_not_count_down() {
+        let (mut engine, clock, sink) = new_engine();
+        clock.advance(Duration::from_secs(90));
+        engine.tick().await.unwrap();
+        assert_eq!(engine.get_remaining_seconds(), 25 * 60);
+        assert!(sink.take().is_empty());
+    }
+
+    #[tokio::test]
+    async fn cycles_focus_short_breaks_and_long_break() {
+        let (mut engine, clock, sink) = new_engine();
+        engine.start().unwrap();
+
+        for completed in 1..=3 {
+            finish_phase(&mut engine, &clock).await;
+            let expected_break = if completed == 3 { TimerState::LongBreak } else { TimerState::ShortBreak };
+            assert_eq!(engine.get_state(), expected_break);
+            assert_eq!(engine.get_completed_pomodoros(), completed);
+            assert_eq!(sink.guard_events(), vec![EngineEvent::ShowGuard]);
+
+            finish_phase(&mut engine, &clock).await;
+            assert_eq!(engine.get_state(), TimerState::Focus);
+            assert!(engine.is_running());
+            assert_eq!(sink.guard_events(), vec![EngineEvent::HideGuard]);
+        }
+    }
+
+    #[tokio::test]
+    async fn focus_minutes_cadence_triggers_long_break() {
+        let clock = FakeClock::new();
+        let sink = Arc::new(RecordingSink::default());
+        let profile = Profile {
+            long_break_cadence: LongBreakCadence::FocusMinutes { minutes: 50 },
+            ..Profile::default()
+        };
+        let mut engine = TimerEngine::new(&profile, Arc::new(clock.clone()), sink);
+        engine.start().unwrap();
+
+        let mut breaks = Vec::new();
+        for _ in 0..4 {
+            finish_phase(&mut engine, &clock).await;
+            breaks.push(engine.get_state());
+            finish_phase(&mut engine, &clock).await;
+        }
+        assert_eq!(breaks, vec![
+            TimerState::ShortBreak,
+            TimerState::LongBreak,
+            TimerState::ShortBreak,
+            TimerState::LongBreak,
+        ]);
+    }
+
+    #[tokio::test]
+    async fn reset_set_restarts_cadence_but_keeps_lifetime_count() {
+        let (mut engine, clock, _) = new_engine();
+        engine.start().unwrap();
+        for _ in 0..2 {
+            finish_phase(&mut engine, &clock).await;
+            finish_phase(&mut engine, &clock).await;
+        }
+        engine.reset_set().unwrap();
+
+        finish_phase(&mut engine, &clock).await;
+        assert_eq!(engine.get_state(), TimerState::ShortBreak);
+        assert_eq!(engine.get_completed_pomodoros(), 3);
+    }
+
+    #[tokio::test]
+    async fn phases_chain_off_deadlines_without_drift() {
+        let (mut engine, clock, _) = new_engine();
+        engine.start().unwrap();
+
+        // A tick arriving five seconds late still ends focus at its deadline.
+        clock.advance(FOCUS + Duration::from_secs(5));
+        engine.tick().await.unwrap();
+        assert_eq!(engine.get_state(), TimerState::ShortBreak);
+        assert_eq!(engine.get_remaining_seconds(), (SHORT_BREAK.as_secs() - 5) as u32);
+    }
+
+    #[tokio::test]
+    async fn pause_freezes_remaining_time() {
+        let (mut engine, clock, _) = new_engine();
+        engine.start().unwrap();
+        clock.advance(Duration::from_secs(100));
+        engine.pause().unwrap();
+
+        clock.advance(Duration::from_secs(3600));
+        engine.tick().await.unwrap();
+        assert_eq!(engine.get_state(), TimerState::Focus);
+        assert_eq!(engine.get_remaining_seconds(), 25 * 60 - 100);
+
+        engine.start().unwrap();
+        clock.advance(FOCUS - Duration::from_secs(100));
+        engine.tick().await.unwrap();
+        assert_eq!(engine.get_state(), TimerState::ShortBreak);
+    }
+
+    #[tokio::test]
+    async fn skip_break_returns_to_running_focus() {
+        let (mut engine, clock, _) = new_engine();
+        engine.start().unwrap();
+        finish_phase(&mut engine, &clock).await;
+        assert_eq!(engine.get_state(), TimerState::ShortBreak);
+
+        clock.advance(Duration::from_secs(30));
+        engine.skip_break(None).unwrap();
+        assert_eq!(engine.get_state(), TimerState::Focus);
+        assert!(engine.is_running());
+        assert_eq!(engine.get_remaining_seconds(), 25 * 60);
+        assert_eq!(engine.get_completed_pomodoros(), 1);
+    }
+
+    #[tokio::test]
+    async fn skip_break_is_ignored_during_focus() {
+        let (mut engine, clock, _) = new_engine();
+        engine.start().unwrap();
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(engine.skip_break(None), Err(SkipDenied::NotInBreak));
+        assert_eq!(engine.get_state(), TimerState::Focus);
+        assert_eq!(engine.get_remaining_seconds(), 24 * 60);
+    }
+
+    #[tokio::test]
+    async fn skip_policy_is_enforced_by_the_engine() {
+        let clock = FakeClock::new();
+        let profile = Profile {
+            skip: SkipPolicy {
+                daily_quota: Some(1),
+                min_elapsed_percent: 50,
+                confirmation_phrase: Some("I choose to skip".to_string()),
+            },
+            ..Profile::default()
+        };
+        let mut engine = TimerEngine::new(&profile, Arc::new(clock.clone()), Arc::new(RecordingSink::default()));
+        engine.start().unwrap();
+        finish_phase(&mut engine, &clock).await;
+
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(
+            engine.skip_break(Some("I choose to skip")),
+            Err(SkipDenied::TooEarly { unlocks_in_seconds: 90 })
+        );
+
+        clock.advance(Duration::from_secs(90));
+        assert_eq!(engine.skip_status().denied, None);
+        assert!(matches!(engine.skip_break(Some("skip")), Err(SkipDenied::ConfirmationRequired { .. })));
+        engine.skip_break(Some("  I choose to skip ")).unwrap();
+        assert_eq!(engine.get_state(), TimerState::Focus);
+        assert_eq!(engine.skip_status().skips_left_today, Some(0));
+
+        finish_phase(&mut engine, &clock).await;
+        clock.advance(Duration::from_secs(200));
+        assert_eq!(
+            engine.skip_break(Some("I choose to skip")),
+            Err(SkipDenied::QuotaExhausted { used: 1, quota: 1 })
+        );
+        assert_eq!(engine.snapshot().skips_on_day, 1);
+    }
+
+    #[tokio::test]
+    async fn strict_profile_refuses_skips() {
+        let clock = FakeClock::new();
+        let profile = Profile { enforcement: Enforcement::Strict, ..Profile::default() };
+        let mut engine = TimerEngine::new(&profile, Arc::new(clock.clone()), Arc::new(RecordingSink::default()));
+        engine.start().unwrap();
+        finish_phase(&mut engine, &clock).await;
+        assert_eq!(engine.skip_break(None), Err(SkipDenied::DisabledByProfile));
+        assert!(engine.get_state().is_break());
+    }
+
+    #[tokio::test]
+    async fn enforced_break_cannot_be_paused() {
+        let (mut engine, clock, _) = new_engine();
+        engine.set_break_lockdown(true);
+        engine.start().unwrap();
+        assert!(engine.allowed_controls().pause);
+        finish_phase(&mut engine, &clock).await;
+
+        assert!(engine.pause().is_err());
+        assert!(engine.is_running());
+        let controls = engine.get_update().controls;
+        assert!(!controls.pause);
+        assert!(!controls.start);
+        assert!(controls.skip);
+        assert!(!controls.extend);
+    }
+
+    #[tokio::test]
+    async fn reset_during_enforced_break_counts_as_a_skip() {
+        let clock = FakeClock::new();
+        let profile = Profile {
+            skip: SkipPolicy { daily_quota: Some(1), ..SkipPolicy::default() },
+            ..Profile::default()
+        };
+        let mut engine = TimerEngine::new(&profile, Arc::new(clock.clone()), Arc::new(RecordingSink::default()));
+        engine.set_break_lockdown(true);
+        engine.start().unwrap();
+        finish_phase(&mut engine, &clock).await;
+
+        engine.reset(None).unwrap();
+        assert_eq!(engine.get_state(), TimerState::Focus);
+        assert_eq!(engine.skip_status().skips_left_today, Some(0));
+
+        engine.start().unwrap();
+        finish_phase(&mut engine, &clock).await;
+        assert!(!engine.allowed_controls().reset);
+        let err = engine.reset(None).unwrap_err();
+        assert_eq!(err.downcast_ref::<SkipDenied>(), Some(&SkipDenied::QuotaExhausted { used: 1, quota: 1 }));
+        assert!(engine.get_state().is_break());
+        assert!(engine.is_running());
+    }
+
+    #[tokio::test]
+    async fn gentle_breaks_stay_pausable_under_lockdown() {
+        let clock = FakeClock::new();
+        let profile = Profile { enforcement: Enforcement::Gentle, ..Profile::default() };
+        let mut engine = TimerEngine::new(&profile, Arc::new(clock.clone()), Arc::new(RecordingSink::default()));
+        engine.set_break_lockdown(true);
+        engine.start().unwrap();
+        finish_phase(&mut engine, &clock).await;
+        assert!(!engine.break_locked());
+        engine.pause().unwrap();
+        assert!(!engine.is_running());
+    }
+
+    #[tokio::test]
+    async fn phase_channel_only_changes_with_the_phase() {
+        let (mut engine, clock, _) = new_engine();
+        let mut phase = engine.subscribe_phase();
+        assert!(!phase.borrow_and_update().is_running);
+
+        engine.start().unwrap();
+        assert!(phase.has_changed().unwrap());
+        assert!(phase.borrow_and_update().is_running);
+
+        clock.advance(Duration::from_secs(30));
+        engine.tick().await.unwrap();
+        assert!(!phase.has_changed().unwrap());
+
+        finish_phase(&mut engine, &clock).await;
+        let status = *phase.borrow_and_update();
+        assert_eq!(status.state, TimerState::ShortBreak);
+        assert!(status.guard_required());
+
+        engine.skip_break(None).unwrap();
+        assert!(!phase.borrow_and_update().guard_required());
+    }
+
+    #[tokio::test]
+    async fn reset_during_break_hides_guard_and_keeps_lifetime_count() {
+        let (mut engine, clock, sink) = new_engine();
+        engine.start().unwrap();
+        finish_phase(&mut engine, &clock).await;
+        sink.take();
+
+        engine.reset(None).unwrap();
+        assert_eq!(engine.get_state(), TimerState::Focus);
+        assert!(!engine.is_running());
+        assert_eq!(engine.get_completed_pomodoros(), 1);
+        assert_eq!(engine.get_remaining_seconds(), 25 * 60);
+        assert_eq!(sink.guard_events(), vec![EngineEvent::HideGuard]);
+    }
+
+    #[tokio::test]
+    async fn transitions_carry_the_state_they_led_to() {
+        let (mut engine, clock, sink) = new_engine();
+        engine.start().unwrap();
+        engine.pause().unwrap();
+        engine.start().unwrap();
+        finish_phase(&mut engine, &clock).await;
+        engine.skip_break(None).unwrap();
+
+        let transitions = sink.transitions();
+        let changes: Vec<PhaseChange> = transitions.iter().map(|t| t.change).collect();
+        // Resuming after the pause is not a new focus phase.
+        assert_eq!(
+            changes,
+            vec![
+                PhaseChange::FocusStarted,
+                PhaseChange::FocusEnded,
+                PhaseChange::BreakStarted,
+                PhaseChange::Skipped,
+                PhaseChange::FocusStarted
+            ]
+        );
+        let skipped = &transitions[3];
+        assert_eq!((skipped.from, skipped.to), (TimerState::ShortBreak, TimerState::Focus));
+        assert_eq!(skipped.update.state, TimerState::Focus);
+        assert_eq!(skipped.update.completed_pomodoros, 1);
+    }
+
+    #[tokio::test]
+    async fn missed_focus_deadline_counts_time_away_as_break() {
+        let (mut engine, clock, sink) = new_engine();
+        engine.start().unwrap();
+
+        clock.advance(FOCUS + SHORT_BREAK + Duration::from_secs(60));
+        engine.tick().await.unwrap();
+        assert_eq!(engine.get_state(), TimerState::Focus);
+        assert!(!engine.is_running());
+        assert_eq!(engine.get_completed_pomodoros(), 1);
+        assert!(sink.guard_events().is_empty());
+    }
+
+    #[tokio::test]
+    async fn missed_focus_deadline_shortens_the_break() {
+        let (mut engine, clock, _) = new_engine();
+        engine.start().unwrap();
+
+        clock.advance(FOCUS + Duration::from_secs(120));
+        engine.tick().await.unwrap();
+        assert_eq!(engine.get_state(), TimerState::ShortBreak);
+        assert_eq!(engine.get_remaining_seconds(), (SHORT_BREAK.as_secs() - 120) as u32);
+    }
+
+    #[tokio::test]
+    async fn missed_break_deadline_waits_in_paused_focus() {
+        let (mut engine, clock, _) = new_engine();
+        engine.start().unwrap();
+        finish_phase(&mut engine, &clock).await;
+
+        clock.advance(SHORT_BREAK + LONG_BREAK);
+        engine.tick().await.unwrap();
+        assert_eq!(engine.get_state(), TimerState::Focus);
+        assert!(!engine.is_running());
+        assert_eq!(engine.get_remaining_seconds(), 25 * 60);
+    }
+
+    #[tokio::test]
+    async fn history_records_completed_skipped_and_reset_phases() {
+        let (mut engine, clock, _) = new_engine();
+        let history = Arc::new(RecordingHistory::default());
+        engine.set_history_recorder(history.clone());
+
+        engine.reset(None).unwrap();
+        engine.start().unwrap();
+        clock.advance(Duration::from_secs(60));
+        engine.pause().unwrap();
+        clock.advance(Duration::from_secs(600));
+        engine.start().unwrap();
+        finish_phase(&mut engine, &clock).await;
+        clock.advance(Duration::from_secs(45));
+        engine.skip_break(None).unwrap();
+        clock.advance(Duration::from_secs(120));
+        engine.reset(None).unwrap();
+
+        let records = history.records.lock().unwrap().clone();
+        let summary: Vec<_> = records
+            .iter()
+            .map(|r| (r.phase, r.outcome, r.planned_seconds, r.actual_seconds))
+            .collect();
+        assert_eq!(summary, vec![
+            (TimerState::Focus, SessionOutcome::Completed, 25 * 60, 25 * 60),
+            (TimerState::ShortBreak, SessionOutcome::Skipped, 5 * 60, 45),
+            (TimerState::Focus, SessionOutcome::Reset, 25 * 60, 120),
+        ]);
+        // The pause stretches the focus phase's wall-clock span but not its running time.
+        assert_eq!((records[0].ended_at - records[0].started_at).num_seconds(), 25 * 60 + 600);
+        assert_eq!(records[1].started_at, records[0].ended_at);
+    }
+
+    #[tokio::test]
+    async fn extend_phase_adds_time_within_cycle_limits() {
+        let (mut engine, clock, _) = new_engine();
+        engine.start().unwrap();
+        clock.advance(FOCUS - Duration::from_secs(30));
+
+        engine.extend_phase(None).unwrap();
+        assert_eq!(engine.get_remaining_seconds(), 30 + 5 * 60);
+        engine.extend_phase(Some(5)).unwrap();
+        assert!(engine.extend_phase(Some(1)).is_err());
+
+        finish_phase(&mut engine, &clock).await;
+        assert_eq!(engine.get_state(), TimerState::ShortBreak);
+        assert!(engine.extend_phase(None).is_err());
+
+        // The allowance starts over with the next cycle.
+        finish_phase(&mut engine, &clock).await;
+        engine.extend_phase(None).unwrap();
+    }
+
+    #[tokio::test]
+    async fn extensions_are_recorded_in_history() {
+        let (mut engine, clock, _) = new_engine();
+        let history = Arc::new(RecordingHistory::default());
+        engine.set_history_recorder(history.clone());
+        engine.start().unwrap();
+        clock.advance(Duration::from_secs(60));
+        engine.extend_phase(None).unwrap();
+        finish_phase(&mut engine, &clock).await;
+
+        let records = history.records.lock().unwrap().clone();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].planned_seconds, 25 * 60);
+        assert_eq!(records[0].actual_seconds, 30 * 60);
+        assert_eq!(records[0].extensions.len(), 1);
+        assert_eq!(records[0].extensions[0].seconds, 5 * 60);
+    }
+
+    #[tokio::test]
+    async fn extension_offer_is_emitted_once_before_break() {
+        let (mut engine, clock, sink) = new_engine();
+        engine.start().unwrap();
+        clock.advance(FOCUS - Duration::from_secs(90));
+        engine.tick().await.unwrap();
+        clock.advance(Duration::from_secs(40));
+        engine.tick().await.unwrap();
+        clock.advance(Duration::from_secs(10));
+        engine.tick().await.unwrap();
+
+        let offers: Vec<_> = sink
+            .take()
+            .into_iter()
+            .filter_map(|event| match event {
+                EngineEvent::ExtensionOffer(offer) => Some(offer),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(offers, vec![ExtensionOffer {
+            remaining_seconds: 50,
+            extension_minutes: 5,
+            extensions_left: 2,
+        }]);
+    }
+
+    #[tokio::test]
+    async fn break_warnings_fire_once_per_threshold() {
+        let (mut engine, clock, sink) = new_engine();
+        engine.set_break_warnings(&[10, 60]);
+        engine.start().unwrap();
+
+        let mut warnings = Vec::new();
+        for _ in 0..(25 * 60 - 1) {
+            clock.advance(Duration::from_secs(1));
+            engine.tick().await.unwrap();
+            warnings.extend(sink.take().into_iter().filter_map(|event| match event {
+                EngineEvent::BreakWarning(warning) => Some(warning.seconds_left),
+                _ => None,
+            }));
+        }
+        assert_eq!(warnings, vec![60, 10]);
+    }
+
+    #[tokio::test]
+    async fn break_warning_announces_upcoming_long_break() {
+        let (mut engine, clock, sink) = new_engine();
+        engine.set_break_warnings(&[60]);
+        engine.start().unwrap();
+        for _ in 0..2 {
+            finish_phase(&mut engine, &clock).await;
+            finish_phase(&mut engine, &clock).await;
+        }
+        sink.take();
+
+        // Jumping past the threshold still warns, naming the long break that follows.
+        clock.advance(FOCUS - Duration::from_secs(5));
+        engine.tick().await.unwrap();
+        let warnings: Vec<_> = sink
+            .take()
+            .into_iter()
+            .filter_map(|event| match event {
+                EngineEvent::BreakWarning(warning) => Some(warning),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(warnings, vec![BreakWarning { seconds_left: 60, next_state: TimerState::LongBreak }]);
+    }
+
+    #[tokio::test]
+    async fn restore_of_running_break_reshows_guard() {
+        let (mut engine, clock, _) = new_engine();
+        engine.start().unwrap();
+        finish_phase(&mut engine, &clock).await;
+        clock.advance(Duration::from_secs(60));
+        let snapshot = engine.snapshot();
+
+        let (mut restored, restored_clock, sink) = new_engine();
+        *restored_clock.now.lock().unwrap() = clock.now() + Duration::from_secs(30);
+        restored.restore(&snapshot).unwrap();
+        assert_eq!(restored.get_state(), TimerState::ShortBreak);
+        assert!(restored.is_running());
+        assert_eq!(restored.get_remaining_seconds(), (SHORT_BREAK.as_secs() - 90) as u32);
+        assert_eq!(restored.get_completed_pomodoros(), 1);
+        assert_eq!(sink.guard_events(), vec![EngineEvent::ShowGuard]);
+    }
+
+    #[tokio::test]
+    async fn restore_keeps_paused_phase_paused() {
+        let (mut engine, clock, _) = new_engine();
+        engine.start().unwrap();
+        clock.advance(Duration::from_secs(300));
+        engine.pause().unwrap();
+        let snapshot = engine.snapshot();
+
+        let (mut restored, restored_clock, sink) = new_engine();
+        restored_clock.advance(Duration::from_secs(86_400));
+        restored.restore(&snapshot).unwrap();
+        assert!(!restored.is_running());
+        assert_eq!(restored.get_remaining_seconds(), 20 * 60);
+        assert!(sink.guard_events().is_empty());
+    }
+
+    #[tokio::test]
+    async fn apply_profile_resizes_only_untouched_phases() {
+        let (mut engine, clock, _) = new_engine();
+        let profile = Profile { focus_minutes: 50, ..Profile::default() };
+        engine.apply_profile(&profile).unwrap();
+        assert_eq!(engine.get_remaining_seconds(), 50 * 60);
+
+        engine.start().unwrap();
+        clock.advance(Duration::from_secs(60));
+        engine.apply_profile(&Profile::default()).unwrap();
+        assert_eq!(engine.get_remaining_seconds(), 49 * 60);
+    }
+
+    #[tokio::test]
+    async fn overridden_phase_lengths_outlast_profile_switches() {
+        let (mut engine, _, _) = new_engine();
+        let lengths = PhaseLengths {
+            focus: Duration::from_secs(10),
+            short_break: Duration::from_secs(8),
+            long_break: Duration::from_secs(12),
+        };
+        engine.override_phase_lengths(lengths);
+        assert_eq!(engine.get_remaining_seconds(), 10);
+
+        engine.apply_profile(&Profile { focus_minutes: 50, ..Profile::default() }).unwrap();
+        assert_eq!(engine.get_remaining_seconds(), 10);
+        assert_eq!(engine.rules.short_break, lengths.short_break);
+    }
+
+    #[tokio::test]
+    async fn apply_profile_keeps_extensions_of_idle_phases() {
+        let (mut engine, _, _) = new_engine();
+        engine.extend_phase(Some(5)).unwrap();
+        assert_eq!(engine.get_remaining_seconds(), 30 * 60);
+
+        engine.apply_profile(&Profile { focus_minutes: 50, ..Profile::default() }).unwrap();
+        assert_eq!(engine.get_remaining_seconds(), 30 * 60);
+        assert_eq!(engine.machine.extensions.len(), 1);
+    }
+}
@@ -0,0 +1,220 @@
+//! Draws the tray icon: a ring that fills clockwise from twelve o'clock as the
+//! phase elapses, coloured by phase. Breaks fill the centre of the ring and a
+//! paused timer shows two bars there instead, with the ring dimmed.
+//!
+//! The output is straight-alpha RGBA, row by row, ready for the tray. Each
+//! pixel is sampled on a 4×4 grid so edges stay smooth at menu bar sizes.
+
+use crate::types::{TimerState, TimerUpdate};
+use std::f64::consts::TAU;
+
+/// Edge length of the rendered icon, in pixels.
+pub const TRAY_ICON_SIZE: u32 = 32;
+/// Progress is drawn in this many steps, so the icon only needs redrawing
+/// when the ring would visibly change.
+pub const PROGRESS_STEPS: u32 = 64;
+const SUBSAMPLES: u32 = 4;
+
+/// Outer radius and ring thickness, as fractions of the icon size.
+const OUTER_RADIUS: f64 = 0.47;
+const RING_WIDTH: f64 = 0.15;
+/// Radius of the filled centre shown during breaks.
+const BREAK_DOT_RADIUS: f64 = 0.2;
+/// Pause bars, as fractions of the icon size.
+const PAUSE_BAR_WIDTH: f64 = 0.09;
+const PAUSE_BAR_HEIGHT: f64 = 0.32;
+const PAUSE_BAR_GAP: f64 = 0.08;
+
+type Rgba = [u8; 4];
+
+const TRACK: Rgba = [128, 128, 128, 90];
+const PAUSED_ALPHA: u8 = 140;
+
+fn phase_colour(state: TimerState) -> Rgba {
+    match state {
+        TimerState::Focus => [229, 72, 77, 255],
+        TimerState::ShortBreak => [48, 164, 108, 255],
+        TimerState::LongBreak => [0, 145, 255, 255],
+    }
+}
+
+/// Everything the icon depends on; equal states draw identical icons.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrayIconState {
+    pub phase: TimerState,
+    pub is_running: bool,
+    /// Elapsed part of the phase, from 0 to [`PROGRESS_STEPS`].
+    pub progress_step: u32,
+}
+
+impl TrayIconState {
+    pub fn new(phase: TimerState, is_running: bool, progress: f64) -> Self {
+        let steps = f64::from(PROGRESS_STEPS);
+        Self {
+            phase,
+            is_running,
+            progress_step: (progress.clamp(0.0, 1.0) * steps).floor() as u32,
+        }
+    }
+
+    pub fn of(update: &TimerUpdate) -> Self {
+        Self::new(update.state, update.is_running, update.progress)
+    }
+}
+
+/// Renders `state` as a `size`×`size` RGBA image.
+pub fn render(state: &TrayIconState, size: u32) -> Vec<u8> {
+    let mut rgba = Vec::with_capacity((size * size * 4) as usize);
+    let scale = f64::from(size);
+    let samples = f64::from(SUBSAMPLES * SUBSAMPLES);
+    for y in 0..size {
+        for x in 0..size {
+            // Premultiplied sums, so transparent samples do not darken the edges
+            let mut sum = [0.0f64; 4];
+            for sy in 0..SUBSAMPLES {
+                for sx in 0..SUBSAMPLES {
+                    let px = (f64::from(x) + (f64::from(sx) + 0.5) / f64::from(SUBSAMPLES)) / scale - 0.5;
+                    let py = (f64::from(y) + (f64::from(sy) + 0.5) / f64::from(SUBSAMPLES)) / scale - 0.5;
+                    if let Some(colour) = sample(state, px, py) {
+                        let alpha = f64::from(colour[3]) / 255.0;
+                        for channel in 0..3 {
+                            sum[channel] += f64::from(colour[channel]) * alpha;
+                        }
+                        sum[3] += alpha;
+                    }
+                }
+            }
+            if sum[3] == 0.0 {
+                rgba.extend_from_slice(&[0, 0, 0, 0]);
+                continue;
+            }
+            for channel in &sum[..3] {
+                rgba.push((channel / sum[3]).round().clamp(0.0, 255.0) as u8);
+            }
+            rgba.push((sum[3] / samples * 255.0).round() as u8);
+        }
+    }
+    rgba
+}
+
+/// The colour at `(x, y)`, measured from the centre in fractions of the icon size.
+fn sample(state: &TrayIconState, x: f64, y: f64) -> Option<Rgba> {
+    let mut colour = phase_colour(state.phase);
+    if !state.is_running {
+        let bar_offset = (PAUSE_BAR_GAP + PAUSE_BAR_WIDTH) / 2.0;
+        if (x.abs() - bar_offset).abs() <= PAUSE_BAR_WIDTH / 2.0 && y.abs() <= PAUSE_BAR_HEIGHT / 2.0 {
+            return Some(colour);
+        }
+        colour[3] = PAUSED_ALPHA;
+    }
+
+    let radius = x.hypot(y);
+    if radius <= BREAK_DOT_RADIUS && state.phase.is_break() && state.is_running {
+        return Some(colour);
+    }
+    if !(OUTER_RADIUS - RING_WIDTH..=OUTER_RADIUS).contains(&radius) {
+        return None;
+    }
+    // Clockwise from twelve o'clock, with y growing downwards
+    let angle = x.atan2(-y).rem_euclid(TAU);
+    let filled = f64::from(state.progress_step) / f64::from(PROGRESS_STEPS) * TAU;
+    if angle < filled {
+        Some(colour)
+    } else {
+        Some(TRACK)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::BufWriter;
+    use std::path::PathBuf;
+
+    const UPDATE_GOLDEN_ENV: &str = "UPDATE_GOLDEN";
+    /// Allowed difference per channel, for floating-point drift between platforms.
+    const TOLERANCE: u8 = 2;
+
+    fn golden_path(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("testdata/tray_icon").join(format!("{}.png", name))
+    }
+
+    fn write_png(path: &PathBuf, rgba: &[u8], size: u32) {
+        let file = File::create(path).expect("failed to create golden image");
+        let mut encoder = png::Encoder::new(BufWriter::new(file), size, size);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().expect("failed to write golden image");
+        writer.write_image_data(rgba).expect("failed to write golden image");
+    }
+
+    fn read_png(path: &PathBuf) -> Option<(Vec<u8>, u32)> {
+        let decoder = png::Decoder::new(File::open(path).ok()?);
+        let mut reader = decoder.read_info().ok()?;
+        let mut rgba = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut rgba).ok()?;
+        (info.color_type == png::ColorType::Rgba && info.width == info.height).then_some((rgba, info.width))
+    }
+
+    fn assert_matches_golden(name: &str, state: TrayIconState) {
+        let rendered = render(&state, TRAY_ICON_SIZE);
+        let path = golden_path(name);
+        if std::env::var_os(UPDATE_GOLDEN_ENV).is_some() {
+            std::fs::create_dir_all(path.parent().unwrap()).expect("failed to create golden directory");
+            write_png(&path, &rendered, TRAY_ICON_SIZE);
+            return;
+        }
+        let (golden, size) = read_png(&path)
+            .unwrap_or_else(|| panic!("{} is missing; run with {}=1 to create it", path.display(), UPDATE_GOLDEN_ENV));
+        assert_eq!(size, TRAY_ICON_SIZE, "{} has the wrong size", path.display());
+        let mismatched = rendered
+            .iter()
+            .zip(&golden)
+            .filter(|(a, b)| a.abs_diff(**b) > TOLERANCE)
+            .count();
+        assert!(
+            mismatched == 0,
+            "{} channels differ from {}; run with {}=1 if the change is intended",
+            mismatched,
+            path.display(),
+            UPDATE_GOLDEN_ENV
+        );
+    }
+
+    #[test]
+    fn focus_icons_match_golden_images() {
+        assert_matches_golden("focus_start", TrayIconState::new(TimerState::Focus, true, 0.0));
+        assert_matches_golden("focus_third", TrayIconState::new(TimerState::Focus, true, 1.0 / 3.0));
+        assert_matches_golden("focus_paused", TrayIconState::new(TimerState::Focus, false, 0.5));
+    }
+
+    #[test]
+    fn break_icons_match_golden_images() {
+        assert_matches_golden("short_break", TrayIconState::new(TimerState::ShortBreak, true, 0.25));
+        assert_matches_golden("long_break", TrayIconState::new(TimerState::LongBreak, true, 0.75));
+        assert_matches_golden("long_break_paused", TrayIconState::new(TimerState::LongBreak, false, 0.75));
+    }
+
+    #[test]
+    fn progress_is_quantized() {
+        let a = TrayIconState::new(TimerState::Focus, true, 0.500);
+        let b = TrayIconState::new(TimerState::Focus, true, 0.505);
+        assert_eq!(a, b);
+        assert_eq!(TrayIconState::new(TimerState::Focus, true, 1.5).progress_step, PROGRESS_STEPS);
+        assert_eq!(TrayIconState::new(TimerState::Focus, true, -1.0).progress_step, 0);
+    }
+
+    #[test]
+    fn icons_are_transparent_outside_the_ring() {
+        let rgba = render(&TrayIconState::new(TimerState::Focus, true, 0.5), TRAY_ICON_SIZE);
+        assert_eq!(rgba.len(), (TRAY_ICON_SIZE * TRAY_ICON_SIZE * 4) as usize);
+        // Corners and, while focusing, the centre stay clear
+        assert_eq!(rgba[3], 0);
+        let centre = ((TRAY_ICON_SIZE / 2 * TRAY_ICON_SIZE + TRAY_ICON_SIZE / 2) * 4 + 3) as usize;
+        assert_eq!(rgba[centre], 0);
+        // The top of the ring is filled with the focus colour
+        let top = ((2 * TRAY_ICON_SIZE + TRAY_ICON_SIZE / 2) * 4) as usize;
+        assert_eq!(rgba[top..top + 4], phase_colour(TimerState::Focus));
+    }
+}
@@ -0,0 +1,88 @@
+//! The line the tray shows as its title and tooltip: the phase and the time
+//! left, followed by the latest break warning until that break starts.
+
+use crate::types::{BreakWarning, TimerUpdate};
+
+#[derive(Debug, Default)]
+pub struct TrayStatus {
+    warning: Option<BreakWarning>,
+}
+
+impl TrayStatus {
+    pub fn warn(&mut self, warning: BreakWarning) {
+        self.warning = Some(warning);
+    }
+
+    /// The status for `update`. A warning is dropped once the break starts, or
+    /// once a reset or an extension leaves more time than it announced.
+    pub fn text(&mut self, update: &TimerUpdate) -> String {
+        let stale = self
+            .warning
+            .as_ref()
+            .is_some_and(|warning| update.state.is_break() || update.remaining_seconds > warning.seconds_left);
+        if stale {
+            self.warning = None;
+        }
+
+        let paused = if update.is_running { "" } else { " (paused)" };
+        let mut text = format!("{} {}{}", update.state.display_name(), update.countdown(), paused);
+        if let Some(warning) = &self.warning {
+            text.push_str(" — ");
+            text.push_str(&warning.message());
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{PhaseChange, PhaseTransition, TimerState};
+
+    fn focus(remaining_seconds: u32) -> TimerUpdate {
+        let mut update = PhaseTransition::sample(PhaseChange::FocusStarted).update;
+        update.state = TimerState::Focus;
+        update.remaining_seconds = remaining_seconds;
+        update.total_seconds = 25 * 60;
+        update
+    }
+
+    fn warning(seconds_left: u32) -> BreakWarning {
+        BreakWarning { seconds_left, next_state: TimerState::ShortBreak }
+    }
+
+    #[test]
+    fn warnings_are_shown_until_the_break_starts() {
+        let mut status = TrayStatus::default();
+        assert_eq!(status.text(&focus(65)), "FOCUS 01:05");
+
+        status.warn(warning(60));
+        assert_eq!(status.text(&focus(60)), "FOCUS 01:00 — Break in 1 min");
+        status.warn(warning(10));
+        assert_eq!(status.text(&focus(9)), "FOCUS 00:09 — Break in 10s");
+
+        let mut on_break = focus(300);
+        on_break.state = TimerState::ShortBreak;
+        assert_eq!(status.text(&on_break), "SHORT BREAK 05:00");
+        // Gone for good, not only while the break lasts
+        assert_eq!(status.text(&focus(9)), "FOCUS 00:09");
+    }
+
+    #[test]
+    fn resets_and_extensions_clear_the_warning() {
+        let mut status = TrayStatus::default();
+        status.warn(warning(60));
+        let mut paused = focus(25 * 60);
+        paused.is_running = false;
+        assert_eq!(status.text(&paused), "FOCUS 25:00 (paused)");
+
+        status.warn(warning(60));
+        assert_eq!(status.text(&focus(5 * 60 + 50)), "FOCUS 05:50");
+    }
+
+    #[test]
+    fn long_breaks_are_named() {
+        let warning = BreakWarning { seconds_left: 90, next_state: TimerState::LongBreak };
+        assert_eq!(warning.message(), "Long break in 90s");
+    }
+}
@@ -0,0 +1,183 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+pub enum TimerState {
+    Focus,
+    ShortBreak,
+    LongBreak,
+}
+
+impl TimerState {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            TimerState::Focus => "FOCUS",
+            TimerState::ShortBreak => "SHORT BREAK",
+            TimerState::LongBreak => "LONG BREAK",
+        }
+    }
+
+    pub fn is_break(&self) -> bool {
+        matches!(self, TimerState::ShortBreak | TimerState::LongBreak)
+    }
+}
+
+/// Sent once when a focus phase is about to end and can still be extended.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+pub struct ExtensionOffer {
+    pub remaining_seconds: u32,
+    pub extension_minutes: u32,
+    pub extensions_left: u32,
+}
+
+/// Sent as a focus phase crosses each configured warning threshold.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+pub struct BreakWarning {
+    pub seconds_left: u32,
+    pub next_state: TimerState,
+}
+
+impl BreakWarning {
+    /// E.g. `Break in 1 min` or `Long break in 10s`.
+    pub fn message(&self) -> String {
+        let break_name = match self.next_state {
+            TimerState::LongBreak => "Long break",
+            _ => "Break",
+        };
+        let when = if self.seconds_left >= 60 && self.seconds_left.is_multiple_of(60) {
+            format!("{} min", self.seconds_left / 60)
+        } else {
+            format!("{}s", self.seconds_left)
+        };
+        format!("{} in {}", break_name, when)
+    }
+}
+
+/// Published on the engine's phase channel; changes only when the phase,
+/// its running state or the break lockdown does, not on every tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PhaseStatus {
+    pub state: TimerState,
+    pub is_running: bool,
+    pub break_locked: bool,
+}
+
+impl PhaseStatus {
+    pub fn guard_required(&self) -> bool {
+        self.state.is_break() && self.is_running
+    }
+}
+
+/// Which timer controls the engine would currently accept.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+pub struct AllowedControls {
+    pub start: bool,
+    pub pause: bool,
+    /// During an enforced break a reset only goes through as a skip.
+    pub reset: bool,
+    /// Confirmation phrases are not considered; see `get_skip_status`.
+    pub skip: bool,
+    pub extend: bool,
+}
+
+/// Where the timer stands within the current long-break set. Only the
+/// `*_per_set` field matching the profile's cadence is present.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+pub struct SetPosition {
+    /// Pomodoros completed since the last long break.
+    pub pomodoros: u32,
+    pub pomodoros_per_set: Option<u32>,
+    #[ts(type = "number")]
+    pub focus_minutes: u64,
+    pub focus_minutes_per_set: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+pub struct TimerUpdate {
+    pub state: TimerState,
+    pub remaining_seconds: u32,
+    /// Length of the current phase, including any extensions.
+    pub total_seconds: u32,
+    /// Fraction of the phase already elapsed, from 0.0 to 1.0.
+    pub progress: f64,
+    /// When the phase will end; only known while running.
+    pub ends_at: Option<DateTime<Utc>>,
+    /// The phase that follows this one if it runs to completion.
+    pub next_state: TimerState,
+    pub set_position: SetPosition,
+    pub completed_pomodoros: u32,
+    pub is_running: bool,
+    pub controls: AllowedControls,
+}
+
+impl TimerUpdate {
+    /// The remaining time as `mm:ss`.
+    pub fn countdown(&self) -> String {
+        format!("{:02}:{:02}", self.remaining_seconds / 60, self.remaining_seconds % 60)
+    }
+}
+
+/// How the timer came to be in its current phase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum PhaseChange {
+    /// A focus phase started running; resuming a paused one does not count.
+    FocusStarted,
+    /// A focus phase ran to completion, whether or not a break follows.
+    FocusEnded,
+    BreakStarted,
+    /// A break ran to completion.
+    BreakEnded,
+    Skipped,
+    Reset,
+}
+
+/// Sent for every [`PhaseChange`], after the state it led to is in place.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+pub struct PhaseTransition {
+    pub change: PhaseChange,
+    pub from: TimerState,
+    pub to: TimerState,
+    pub at: DateTime<Utc>,
+    pub update: TimerUpdate,
+}
+
+#[cfg(test)]
+impl PhaseTransition {
+    /// The end of a first pomodoro, reported as `change`.
+    pub fn sample(change: PhaseChange) -> Self {
+        Self {
+            change,
+            from: TimerState::Focus,
+            to: TimerState::ShortBreak,
+            at: Utc::now(),
+            update: TimerUpdate {
+                state: TimerState::ShortBreak,
+                remaining_seconds: 5 * 60,
+                total_seconds: 5 * 60,
+                progress: 0.0,
+                ends_at: None,
+                next_state: TimerState::Focus,
+                set_position: SetPosition {
+                    pomodoros: 1,
+                    pomodoros_per_set: Some(4),
+                    focus_minutes: 25,
+                    focus_minutes_per_set: None,
+                },
+                completed_pomodoros: 1,
+                is_running: true,
+                controls: AllowedControls::default(),
+            },
+        }
+    }
+}
+
+/// The name `value` is serialized under, e.g. `break_started` for
+/// [`PhaseChange::BreakStarted`]; empty for values that are not plain strings.
+pub fn serde_name<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(name)) => name,
+        _ => String::new(),
+    }
+}
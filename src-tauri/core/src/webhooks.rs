@@ -0,0 +1,565 @@
+//! Sends each phase transition to the configured webhook targets as a signed
+//! JSON POST. Deliveries wait in a bounded queue that is saved to disk, so
+//! ones still pending when the app quits are sent after the next start.
+//! Each delivery is sent on its own schedule, so a slow or unreachable target
+//! never holds up the others; failed attempts are retried with exponential
+//! backoff.
+//!
+//! Every request carries these headers:
+//! - `X-TimeToThink-Event`: the `PhaseChange`, e.g. `break_started`
+//! - `X-TimeToThink-Delivery`: an id that stays the same across retries
+//! - `X-TimeToThink-Timestamp`: Unix seconds when the attempt was made
+//! - `X-TimeToThink-Signature`: `sha256=` and the hex HMAC-SHA256 of
+//!   `"{timestamp}.{body}"`, keyed with the target's secret
+
+use crate::events::EngineEvent;
+use crate::settings::{SharedSettings, WebhookTarget};
+use crate::storage::{read_json, write_json_atomic};
+use crate::types::{serde_name, PhaseChange, PhaseTransition};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashSet, VecDeque};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{broadcast, Notify};
+use tokio::task::JoinSet;
+use ts_rs::TS;
+
+pub const WEBHOOK_QUEUE_FILE_NAME: &str = "webhook_queue.json";
+pub const DEFAULT_QUEUE_CAPACITY: usize = 500;
+pub const EVENT_HEADER: &str = "X-TimeToThink-Event";
+pub const DELIVERY_HEADER: &str = "X-TimeToThink-Delivery";
+pub const TIMESTAMP_HEADER: &str = "X-TimeToThink-Timestamp";
+pub const SIGNATURE_HEADER: &str = "X-TimeToThink-Signature";
+const MAX_ATTEMPTS: u32 = 8;
+const FIRST_RETRY_SECONDS: i64 = 5;
+const MAX_RETRY_SECONDS: i64 = 30 * 60;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_LOG_ENTRIES: usize = 200;
+/// How long to wait for the first delivery when nothing is queued.
+const IDLE_WAIT: Duration = Duration::from_secs(60 * 60);
+
+/// One transition on its way to one target.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Delivery {
+    id: String,
+    url: String,
+    event: PhaseChange,
+    /// Serialized once so every attempt signs and sends the same bytes.
+    body: String,
+    attempts: u32,
+    next_attempt_at: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+struct Payload<'a> {
+    id: &'a str,
+    transition: &'a PhaseTransition,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryOutcome {
+    Delivered,
+    /// The attempt failed and another is scheduled.
+    Retrying,
+    /// The last attempt failed; the delivery was given up.
+    Failed,
+    /// Never sent: the queue overflowed or the target was removed.
+    Dropped,
+}
+
+/// A delivery attempt, as shown by the delivery log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+pub struct DeliveryLogEntry {
+    pub id: String,
+    pub url: String,
+    pub event: PhaseChange,
+    pub attempt: u32,
+    pub at: DateTime<Utc>,
+    pub outcome: DeliveryOutcome,
+    pub status_code: Option<u16>,
+    pub error: Option<String>,
+}
+
+struct Queue {
+    pending: VecDeque<Delivery>,
+    /// Ids of the deliveries with an attempt under way.
+    in_flight: HashSet<String>,
+    log: VecDeque<DeliveryLogEntry>,
+    next_id: u64,
+}
+
+impl Queue {
+    fn record(&mut self, delivery: &Delivery, at: DateTime<Utc>, outcome: DeliveryOutcome, result: AttemptResult) {
+        if self.log.len() == MAX_LOG_ENTRIES {
+            self.log.pop_front();
+        }
+        self.log.push_back(DeliveryLogEntry {
+            id: delivery.id.clone(),
+            url: delivery.url.clone(),
+            event: delivery.event,
+            attempt: delivery.attempts,
+            at,
+            outcome,
+            status_code: result.status_code,
+            error: result.error,
+        });
+    }
+}
+
+#[derive(Debug, Default)]
+struct AttemptResult {
+    status_code: Option<u16>,
+    error: Option<String>,
+}
+
+type Attempted = (DeliveryOutcome, AttemptResult);
+
+pub struct WebhookDispatcher {
+    path: PathBuf,
+    capacity: usize,
+    client: reqwest::Client,
+    queue: Mutex<Queue>,
+    wake: Notify,
+}
+
+impl WebhookDispatcher {
+    /// Picks up the deliveries saved at `path` by a previous run.
+    pub fn load(path: PathBuf, capacity: usize) -> Self {
+        let pending: VecDeque<Delivery> = match read_json(&path) {
+            Ok(pending) => pending.unwrap_or_default(),
+            Err(e) => {
+                eprintln!("Ignoring webhook queue {}: {}", path.display(), e);
+                VecDeque::new()
+            }
+        };
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Self {
+            path,
+            capacity,
+            client,
+            queue: Mutex::new(Queue { pending, in_flight: HashSet::new(), log: VecDeque::new(), next_id: 0 }),
+            wake: Notify::new(),
+        }
+    }
+
+    /// Recent attempts, newest first.
+    pub fn deliveries(&self) -> Vec<DeliveryLogEntry> {
+        self.lock().log.iter().rev().cloned().collect()
+    }
+
+    pub fn pending_count(&self) -> usize {
+        self.lock().pending.len()
+    }
+
+    /// Queues `transition` for every target that wants it, dropping the oldest
+    /// deliveries once the queue is full.
+    pub fn enqueue(&self, targets: &[WebhookTarget], transition: &PhaseTransition) {
+        let mut queue = self.lock();
+        for target in targets.iter().filter(|target| target.wants(transition.change)) {
+            let id = format!("{:x}-{}", transition.at.timestamp_micros(), queue.next_id);
+            queue.next_id += 1;
+            let body = match serde_json::to_string(&Payload { id: &id, transition }) {
+                Ok(body) => body,
+                Err(e) => {
+                    eprintln!("[Webhooks] Failed to serialize transition: {}", e);
+                    return;
+                }
+            };
+            if queue.pending.len() == self.capacity {
+                if let Some(oldest) = queue.pending.pop_front() {
+                    let error = Some("queue full".to_string());
+                    queue.record(&oldest, transition.at, DeliveryOutcome::Dropped, AttemptResult { status_code: None, error });
+                }
+            }
+            queue.pending.push_back(Delivery {
+                id,
+                url: target.url.clone(),
+                event: transition.change,
+                body,
+                attempts: 0,
+                next_attempt_at: transition.at,
+            });
+        }
+        self.save(&queue);
+        drop(queue);
+        self.wake.notify_one();
+    }
+
+    /// Attempts every delivery due at `now` at the same time and waits for
+    /// them all. `targets` supplies the secrets; deliveries to removed targets
+    /// are dropped. Deliveries already being attempted are left alone.
+    pub async fn deliver_due(&self, targets: &[WebhookTarget], now: DateTime<Utc>) {
+        let attempts = self.start_due(targets, now);
+        self.finish_all(attempts, now).await;
+    }
+
+    fn start_due(&self, targets: &[WebhookTarget], now: DateTime<Utc>) -> JoinSet<(String, Attempted)> {
+        let due: Vec<Delivery> = {
+            let mut queue = self.lock();
+            let due: Vec<Delivery> = queue
+                .pending
+                .iter()
+                .filter(|delivery| delivery.next_attempt_at <= now && !queue.in_flight.contains(&delivery.id))
+                .cloned()
+                .collect();
+            queue.in_flight.extend(due.iter().map(|delivery| delivery.id.clone()));
+            due
+        };
+
+        let mut attempts = JoinSet::new();
+        for delivery in due {
+            let client = self.client.clone();
+            let secret = targets.iter().find(|target| target.url == delivery.url).map(|target| target.secret.clone());
+            attempts.spawn(async move {
+                let attempted = match secret {
+                    Some(secret) => attempt(&client, &delivery, &secret, now).await,
+                    None => {
+                        let error = Some("target removed from settings".to_string());
+                        (DeliveryOutcome::Dropped, AttemptResult { status_code: None, error })
+                    }
+                };
+                (delivery.id, attempted)
+            });
+        }
+        attempts
+    }
+
+    async fn finish_all(&self, mut attempts: JoinSet<(String, Attempted)>, now: DateTime<Utc>) {
+        while let Some(finished) = attempts.join_next().await {
+            match finished {
+                Ok((id, (outcome, result))) => self.finish_attempt(&id, now, outcome, result),
+                Err(e) => eprintln!("[Webhooks] Delivery task failed: {}", e),
+            }
+        }
+    }
+
+    /// Records how an attempt went and schedules the retry, if there is one.
+    fn finish_attempt(&self, id: &str, now: DateTime<Utc>, outcome: DeliveryOutcome, result: AttemptResult) {
+        let mut queue = self.lock();
+        queue.in_flight.remove(id);
+        // Gone if the queue overflowed while the attempt was under way
+        let Some(index) = queue.pending.iter().position(|pending| pending.id == id) else {
+            return;
+        };
+        queue.pending[index].attempts += 1;
+        let mut delivery = queue.pending[index].clone();
+        if delivery.attempts >= MAX_ATTEMPTS && outcome == DeliveryOutcome::Retrying {
+            queue.record(&delivery, now, DeliveryOutcome::Failed, result);
+            queue.pending.remove(index);
+        } else if outcome == DeliveryOutcome::Retrying {
+            delivery.next_attempt_at = now + retry_delay(delivery.attempts);
+            queue.pending[index] = delivery.clone();
+            queue.record(&delivery, now, outcome, result);
+        } else {
+            queue.record(&delivery, now, outcome, result);
+            queue.pending.remove(index);
+        }
+        self.save(&queue);
+        drop(queue);
+        // The run loop may be waiting on a deadline this attempt has moved
+        self.wake.notify_one();
+    }
+
+    /// Queues transitions as they arrive and delivers them until the event
+    /// channel closes. Targets are read from the settings on every pass, and a
+    /// pass starts whatever is due without waiting for attempts still under way.
+    pub async fn run(self: Arc<Self>, settings: SharedSettings, events: broadcast::Receiver<EngineEvent>) {
+        let listener = tokio::spawn(self.clone().listen(settings.clone(), events));
+        while !listener.is_finished() {
+            let targets = settings.lock().await.get().webhooks.clone();
+            let now = Utc::now();
+            let attempts = self.start_due(&targets, now);
+            let dispatcher = self.clone();
+            tokio::spawn(async move { dispatcher.finish_all(attempts, now).await });
+
+            let wait = self
+                .next_due()
+                .map(|due| (due - Utc::now()).to_std().unwrap_or(Duration::ZERO))
+                .unwrap_or(IDLE_WAIT);
+            let _ = tokio::time::timeout(wait, self.wake.notified()).await;
+        }
+    }
+
+    async fn listen(self: Arc<Self>, settings: SharedSettings, mut events: broadcast::Receiver<EngineEvent>) {
+        loop {
+            match events.recv().await {
+                Ok(EngineEvent::PhaseTransition(transition)) => {
+                    let targets = settings.lock().await.get().webhooks.clone();
+                    self.enqueue(&targets, &transition);
+                }
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    eprintln!("[Webhooks] Missed {} events", missed);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+        self.wake.notify_one();
+    }
+
+    /// When the next delivery that is not already under way is due.
+    fn next_due(&self) -> Option<DateTime<Utc>> {
+        let queue = self.lock();
+        queue
+            .pending
+            .iter()
+            .filter(|delivery| !queue.in_flight.contains(&delivery.id))
+            .map(|delivery| delivery.next_attempt_at)
+            .min()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Queue> {
+        self.queue.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn save(&self, queue: &Queue) {
+        if let Err(e) = write_json_atomic(&self.path, &queue.pending) {
+            eprintln!("[Webhooks] Failed to save queue: {}", e);
+        }
+    }
+}
+
+/// One attempt at `delivery`, signed with `secret`.
+async fn attempt(
+    client: &reqwest::Client,
+    delivery: &Delivery,
+    secret: &str,
+    now: DateTime<Utc>,
+) -> Attempted {
+    let timestamp = now.timestamp().to_string();
+    let response = client
+        .post(&delivery.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(EVENT_HEADER, serde_name(&delivery.event))
+        .header(DELIVERY_HEADER, &delivery.id)
+        .header(SIGNATURE_HEADER, signature(secret, &timestamp, &delivery.body))
+        .header(TIMESTAMP_HEADER, timestamp)
+        .body(delivery.body.clone())
+        .send()
+        .await;
+    match response {
+        Ok(response) if response.status().is_success() => (
+            DeliveryOutcome::Delivered,
+            AttemptResult { status_code: Some(response.status().as_u16()), error: None },
+        ),
+        Ok(response) => (
+            DeliveryOutcome::Retrying,
+            AttemptResult {
+                status_code: Some(response.status().as_u16()),
+                error: Some(format!("HTTP {}", response.status())),
+            },
+        ),
+        Err(e) => (DeliveryOutcome::Retrying, AttemptResult { status_code: None, error: Some(e.to_string()) }),
+    }
+}
+
+/// 5s after the first failure, doubling up to 30 minutes.
+fn retry_delay(attempts: u32) -> chrono::Duration {
+    let seconds = FIRST_RETRY_SECONDS.saturating_mul(1 << attempts.saturating_sub(1).min(16));
+    chrono::Duration::seconds(seconds.min(MAX_RETRY_SECONDS))
+}
+
+/// The `X-TimeToThink-Signature` value for `body` sent at `timestamp`.
+pub fn signature(secret: &str, timestamp: &str, body: &str) -> String {
+    let message = format!("{}.{}", timestamp, body);
+    let mac = hmac_sha256(secret.as_bytes(), message.as_bytes());
+    format!("sha256={}", mac.iter().map(|byte| format!("{:02x}", byte)).collect::<String>())
+}
+
+/// HMAC as in RFC 2104.
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    const BLOCK_SIZE: usize = 64;
+    let mut block = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let pad = |byte: u8| block.map(|key_byte| key_byte ^ byte);
+    let inner = Sha256::new().chain_update(pad(0x36)).chain_update(message).finalize();
+    Sha256::new().chain_update(pad(0x5c)).chain_update(inner).finalize().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::State;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::routing::post;
+    use axum::Router;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const SECRET: &str = "s3cret";
+
+    /// Records every request and answers the first `failures` with a 500.
+    #[derive(Clone, Default)]
+    struct Stub {
+        received: Arc<Mutex<Vec<(HeaderMap, String)>>>,
+        failures: Arc<AtomicUsize>,
+    }
+
+    async fn receive(State(stub): State<Stub>, headers: HeaderMap, body: String) -> StatusCode {
+        stub.received.lock().unwrap().push((headers, body));
+        let fail = stub
+            .failures
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| left.checked_sub(1))
+            .is_ok();
+        if fail {
+            StatusCode::INTERNAL_SERVER_ERROR
+        } else {
+            StatusCode::NO_CONTENT
+        }
+    }
+
+    async fn start_stub(failures: usize) -> (Stub, WebhookTarget) {
+        let stub = Stub::default();
+        stub.failures.store(failures, Ordering::SeqCst);
+        let app = Router::new().route("/hook", post(receive)).with_state(stub.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        let target = WebhookTarget { url, secret: SECRET.to_string(), events: Vec::new() };
+        (stub, target)
+    }
+
+    #[test]
+    fn hmac_matches_rfc_4231() {
+        let mac = hmac_sha256(b"Jefe", b"what do ya want for nothing?");
+        let hex: String = mac.iter().map(|byte| format!("{:02x}", byte)).collect();
+        assert_eq!(hex, "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+    }
+
+    #[test]
+    fn retries_back_off_up_to_a_cap() {
+        assert_eq!(retry_delay(1), chrono::Duration::seconds(5));
+        assert_eq!(retry_delay(2), chrono::Duration::seconds(10));
+        assert_eq!(retry_delay(40), chrono::Duration::seconds(MAX_RETRY_SECONDS));
+    }
+
+    #[tokio::test]
+    async fn deliveries_are_signed() {
+        let dir = tempfile::tempdir().unwrap();
+        let (stub, target) = start_stub(0).await;
+        let dispatcher = WebhookDispatcher::load(dir.path().join(WEBHOOK_QUEUE_FILE_NAME), 10);
+
+        dispatcher.enqueue(std::slice::from_ref(&target), &PhaseTransition::sample(PhaseChange::BreakStarted));
+        dispatcher.deliver_due(&[target], Utc::now()).await;
+
+        let received = stub.received.lock().unwrap().clone();
+        assert_eq!(received.len(), 1);
+        let (headers, body) = &received[0];
+        assert_eq!(headers[EVENT_HEADER], "break_started");
+        let timestamp = headers[TIMESTAMP_HEADER].to_str().unwrap();
+        assert_eq!(headers[SIGNATURE_HEADER], signature(SECRET, timestamp, body).as_str());
+        let payload: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(payload["id"], headers[DELIVERY_HEADER].to_str().unwrap());
+        assert_eq!(payload["transition"]["change"], "break_started");
+
+        assert_eq!(dispatcher.pending_count(), 0);
+        assert_eq!(dispatcher.deliveries()[0].outcome, DeliveryOutcome::Delivered);
+    }
+
+    #[tokio::test]
+    async fn failed_deliveries_are_retried_later() {
+        let dir = tempfile::tempdir().unwrap();
+        let (stub, target) = start_stub(1).await;
+        let targets = [target];
+        let dispatcher = WebhookDispatcher::load(dir.path().join(WEBHOOK_QUEUE_FILE_NAME), 10);
+        let transition = PhaseTransition::sample(PhaseChange::FocusStarted);
+        dispatcher.enqueue(&targets, &transition);
+
+        dispatcher.deliver_due(&targets, transition.at).await;
+        assert_eq!(dispatcher.deliveries()[0].outcome, DeliveryOutcome::Retrying);
+        assert_eq!(dispatcher.deliveries()[0].status_code, Some(500));
+
+        // Not due yet.
+        dispatcher.deliver_due(&targets, transition.at + chrono::Duration::seconds(1)).await;
+        assert_eq!(stub.received.lock().unwrap().len(), 1);
+
+        dispatcher.deliver_due(&targets, transition.at + chrono::Duration::minutes(1)).await;
+        let deliveries = dispatcher.deliveries();
+        assert_eq!(deliveries[0].outcome, DeliveryOutcome::Delivered);
+        assert_eq!(deliveries[0].attempt, 2);
+        let received = stub.received.lock().unwrap();
+        assert_eq!(received[0].0[DELIVERY_HEADER], received[1].0[DELIVERY_HEADER]);
+    }
+
+    #[tokio::test]
+    async fn queue_survives_restarts_and_stays_bounded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(WEBHOOK_QUEUE_FILE_NAME);
+        let (stub, target) = start_stub(0).await;
+        let targets = [WebhookTarget { events: vec![PhaseChange::BreakStarted], ..target }];
+
+        let dispatcher = WebhookDispatcher::load(path.clone(), 2);
+        for _ in 0..3 {
+            dispatcher.enqueue(&targets, &PhaseTransition::sample(PhaseChange::BreakStarted));
+        }
+        dispatcher.enqueue(&targets, &PhaseTransition::sample(PhaseChange::Reset));
+        assert_eq!(dispatcher.pending_count(), 2);
+        assert_eq!(dispatcher.deliveries()[0].outcome, DeliveryOutcome::Dropped);
+        drop(dispatcher);
+
+        let restarted = WebhookDispatcher::load(path, 2);
+        assert_eq!(restarted.pending_count(), 2);
+        restarted.deliver_due(&targets, Utc::now()).await;
+        assert_eq!(stub.received.lock().unwrap().len(), 2);
+        assert_eq!(restarted.pending_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn unresponsive_targets_do_not_hold_up_others() {
+        let dir = tempfile::tempdir().unwrap();
+        // Accepts connections but never answers, so each attempt runs until it times out
+        let hanging = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let dead = WebhookTarget {
+            url: format!("http://{}/hook", hanging.local_addr().unwrap()),
+            secret: SECRET.to_string(),
+            events: Vec::new(),
+        };
+        let (stub, healthy) = start_stub(0).await;
+        let targets = [dead, healthy];
+        let dispatcher = Arc::new(WebhookDispatcher::load(dir.path().join(WEBHOOK_QUEUE_FILE_NAME), 10));
+        let transition = PhaseTransition::sample(PhaseChange::BreakStarted);
+        dispatcher.enqueue(&targets[..1], &transition);
+        dispatcher.enqueue(&targets, &transition);
+
+        let delivering = dispatcher.clone();
+        let attempts = targets.clone();
+        let pass = tokio::spawn(async move { delivering.deliver_due(&attempts, transition.at).await });
+        let delivered = async {
+            while stub.received.lock().unwrap().is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        };
+        tokio::time::timeout(REQUEST_TIMEOUT / 2, delivered)
+            .await
+            .expect("the healthy target waited for the unresponsive one");
+        assert!(!pass.is_finished());
+
+        // A pass started meanwhile leaves the attempts under way alone
+        dispatcher.deliver_due(&targets, transition.at).await;
+        assert_eq!(stub.received.lock().unwrap().len(), 1);
+        pass.abort();
+    }
+
+    #[tokio::test]
+    async fn deliveries_to_removed_targets_are_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let (stub, target) = start_stub(0).await;
+        let dispatcher = WebhookDispatcher::load(dir.path().join(WEBHOOK_QUEUE_FILE_NAME), 10);
+        dispatcher.enqueue(&[target], &PhaseTransition::sample(PhaseChange::Skipped));
+
+        dispatcher.deliver_due(&[], Utc::now()).await;
+        assert!(stub.received.lock().unwrap().is_empty());
+        assert_eq!(dispatcher.pending_count(), 0);
+        assert_eq!(dispatcher.deliveries()[0].outcome, DeliveryOutcome::Dropped);
+    }
+}
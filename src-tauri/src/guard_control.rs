@@ -1,11 +1,6 @@
-use tauri::{ActivationPolicy, AppHandle, Manager, WebviewUrl, WebviewWindow, WebviewWindowBuilder};
-
-const KIOSK_MODE_ENV: &str = "TTT_KIOSK_MODE";
-
-fn kiosk_mode_enabled() -> bool {
-    let raw = std::env::var(KIOSK_MODE_ENV).unwrap_or_else(|_| "1".to_string());
-    !matches!(raw.trim().to_ascii_lowercase().as_str(), "0" | "false" | "no" | "off")
-}
+use tauri::{ActivationPolicy, AppHandle, Manager, WebviewUrl, WebviewWindow, WebviewWindowBuilder, WindowEvent};
+use timetothink_core::types::PhaseStatus;
+use tokio::sync::watch;
 
 #[cfg(target_os = "macos")]
 fn apply_macos_presentation_options(app: &AppHandle, enabled: bool) {
@@ -68,12 +63,16 @@ fn stretch_guard_to_monitor(app: &AppHandle, guard_window: &WebviewWindow) -> Re
     Ok(())
 }
 
-pub async fn show_guard(app: &AppHandle) -> Result<(), String> {
+/// Shows the guard over the whole screen. `lockdown` additionally hides the
+/// dock and menu bar and blocks app switching on macOS.
+pub async fn show_guard(app: &AppHandle, lockdown: bool) -> Result<(), String> {
     #[cfg(target_os = "macos")]
     {
         let _ = app.set_activation_policy(ActivationPolicy::Regular);
-        apply_macos_presentation_options(app, kiosk_mode_enabled());
+        apply_macos_presentation_options(app, lockdown);
     }
+    #[cfg(not(target_os = "macos"))]
+    let _ = lockdown;
 
     if let Some(main_window) = app.get_webview_window("main") {
         let _ = main_window.hide();
@@ -101,6 +100,16 @@ pub async fn show_guard(app: &AppHandle) -> Result<(), String> {
     .map_err(|e| e.to_string())?;
     stretch_guard_to_monitor(app, &guard)?;
 
+    // Take focus straight back while the guard is up instead of polling for it
+    let handle = app.clone();
+    guard.on_window_event(move |event| {
+        if let WindowEvent::Focused(false) = event {
+            if let Err(e) = refocus_guard(&handle) {
+                eprintln!("Guard refocus error: {}", e);
+            }
+        }
+    });
+
     Ok(())
 }
 
@@ -123,37 +132,38 @@ pub async fn hide_guard(app: &AppHandle) -> Result<(), String> {
     Ok(())
 }
 
-pub async fn ensure_guard_focused(app: &AppHandle) -> Result<(), String> {
+/// Pulls a visible guard back to the front after it lost focus. A hidden
+/// guard is left alone, since hiding it also takes its focus away.
+fn refocus_guard(app: &AppHandle) -> Result<(), String> {
     if let Some(guard_window) = app.get_webview_window("breakguard") {
         if !guard_window.is_visible().map_err(|e| e.to_string())? {
-            guard_window.show().map_err(|e| e.to_string())?;
+            return Ok(());
         }
-        stretch_guard_to_monitor(app, &guard_window)?;
-        guard_window.set_focus().map_err(|e| e.to_string())?;
         guard_window.set_always_on_top(true).map_err(|e| e.to_string())?;
+        guard_window.set_focus().map_err(|e| e.to_string())?;
     }
     Ok(())
 }
 
-pub async fn start_guard_polling(app: AppHandle, engine: crate::timer_engine::SharedTimerEngine) {
-    let mut interval = tokio::time::interval(tokio::time::Duration::from_millis(500));
+/// Shows the guard while a break runs and hides it otherwise, reacting to each
+/// change the engine publishes. Returns once the engine is dropped.
+pub async fn run_guard_controller(app: AppHandle, mut phase: watch::Receiver<PhaseStatus>) {
     loop {
-        interval.tick().await;
-
-        let engine_guard = engine.lock().await;
-        let is_break = engine_guard.get_state().is_break() && engine_guard.is_running();
-        drop(engine_guard);
-
-        if is_break {
-            if let Err(e) = ensure_guard_focused(&app).await {
-                eprintln!("Guard polling error: {}", e);
-            }
-        } else if let Some(guard_window) = app.get_webview_window("breakguard") {
-            if let Ok(true) = guard_window.is_visible() {
-                if let Err(e) = hide_guard(&app).await {
-                    eprintln!("Guard hide polling error: {}", e);
-                }
+        let status = *phase.borrow_and_update();
+        let result = if status.guard_required() {
+            show_guard(&app, status.break_locked).await
+        } else {
+            match app.get_webview_window("breakguard") {
+                Some(guard_window) if guard_window.is_visible().unwrap_or(false) => hide_guard(&app).await,
+                _ => Ok(()),
             }
+        };
+        if let Err(e) = result {
+            eprintln!("Guard controller error: {}", e);
+        }
+
+        if phase.changed().await.is_err() {
+            break;
         }
     }
 }
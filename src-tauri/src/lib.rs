@@ -1,3 +0,0 @@
-mod guard_control;
-mod timer_engine;
-mod types;
@@ -1,26 +1,83 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 mod guard_control;
-mod timer_engine;
-mod types;
+mod shortcuts;
+mod tauri_sink;
+mod tray;
 
-use guard_control::{hide_guard, show_guard, start_guard_polling};
+use chrono::{DateTime, Utc};
+use guard_control::run_guard_controller;
+use shortcuts::ShortcutRegistry;
 use std::sync::Arc;
-use tauri::image::Image;
-use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use std::time::Duration;
 use tauri::{ActivationPolicy, AppHandle, Listener, Manager, RunEvent, State};
-use types::TimerUpdate;
-use timer_engine::{start_timer_loop, SharedTimerEngine, TimerEngine};
+use tauri_plugin_notification::NotificationExt;
+use tauri_sink::TauriEventSink;
+use tray::TRAY_TOOLTIP;
+use timetothink_core::clock::SystemClock;
+use timetothink_core::engine_actor::{self, EngineHandle, EngineStopped};
+use timetothink_core::events::{BroadcastSink, EventName, EventSink, FanOutSink};
+use timetothink_core::history::{HistoryLog, SessionRecord, HISTORY_FILE_NAME};
+use timetothink_core::hooks::{self, HookOutcome};
+use timetothink_core::http_api::{self, ApiState};
+#[cfg(unix)]
+use timetothink_core::ipc;
+use timetothink_core::session_store::{FileSnapshotStore, SESSION_FILE_NAME};
+use timetothink_core::settings::{Profile, Settings, SettingsStore, SharedSettings, SETTINGS_FILE_NAME};
+use timetothink_core::shortcuts::ShortcutBinding;
+use timetothink_core::skip_policy::{SkipDenied, SkipStatus};
+use timetothink_core::service;
+use timetothink_core::stats::{DailyStats, StatsSummary, WeeklyStats};
+use timetothink_core::state_machine::PhaseLengths;
+use timetothink_core::timer_engine::TimerEngine;
+use timetothink_core::types::{BreakWarning, PhaseChange, TimerUpdate};
+use timetothink_core::webhooks::{
+    DeliveryLogEntry, WebhookDispatcher, DEFAULT_QUEUE_CAPACITY, WEBHOOK_QUEUE_FILE_NAME,
+};
 use tokio::sync::Mutex;
 
-const FALLBACK_TRAY_ICON: [u8; 4] = [255, 255, 255, 255];
+/// Events buffered for each tray, shortcut, hook, webhook or HTTP API
+/// subscriber before a slow reader starts missing some.
+const ENGINE_EVENT_BUFFER: usize = 64;
 const KIOSK_MODE_ENV: &str = "TTT_KIOSK_MODE";
+const TEST_MODE_ENV: &str = "TTT_TEST_MODE";
+/// Phase lengths in test mode, short enough to watch a whole cycle go by.
+const TEST_PHASE_LENGTHS: PhaseLengths = PhaseLengths {
+    focus: Duration::from_secs(10),
+    short_break: Duration::from_secs(8),
+    long_break: Duration::from_secs(12),
+};
 
 fn kiosk_mode_enabled() -> bool {
     let raw = std::env::var(KIOSK_MODE_ENV).unwrap_or_else(|_| "1".to_string());
     !matches!(raw.trim().to_ascii_lowercase().as_str(), "0" | "false" | "no" | "off")
 }
 
+fn test_mode_enabled() -> bool {
+    let raw = std::env::var(TEST_MODE_ENV).unwrap_or_default();
+    matches!(raw.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes" | "on")
+}
+
+fn show_main_window(app: &AppHandle) {
+    if let Some(main_window) = app.get_webview_window("main") {
+        let _ = main_window.show();
+        let _ = main_window.set_focus();
+    }
+}
+
+/// The tray shows the same warning next to its countdown.
+fn announce_break_warning(app: &AppHandle, warning: &BreakWarning) {
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title(TRAY_TOOLTIP)
+        .body(format!("{}. Save your work.", warning.message()))
+        .show()
+    {
+        eprintln!("Failed to show break warning notification: {}", e);
+    }
+}
+
 fn toggle_main_window(app: &AppHandle) {
     if let Some(main_window) = app.get_webview_window("main") {
         let is_visible = main_window.is_visible().unwrap_or(false);
@@ -36,44 +93,184 @@ fn toggle_main_window(app: &AppHandle) {
 }
 
 #[tauri::command]
-async fn start_timer(engine: State<'_, SharedTimerEngine>, app: AppHandle) -> Result<(), String> {
-    let mut engine_guard = engine.lock().await;
-    engine_guard.start(&app).map_err(|e| e.to_string())?;
+async fn start_timer(engine: State<'_, EngineHandle>) -> Result<(), String> {
+    engine.start().await??;
     Ok(())
 }
 
 #[tauri::command]
-async fn pause_timer(engine: State<'_, SharedTimerEngine>, app: AppHandle) -> Result<(), String> {
-    let mut engine_guard = engine.lock().await;
-    engine_guard.pause(&app).map_err(|e| e.to_string())?;
+async fn pause_timer(engine: State<'_, EngineHandle>) -> Result<(), String> {
+    engine.pause().await??;
     Ok(())
 }
 
 #[tauri::command]
-async fn reset_timer(engine: State<'_, SharedTimerEngine>, app: AppHandle) -> Result<(), String> {
-    let mut engine_guard = engine.lock().await;
-    engine_guard.reset(&app).map_err(|e| e.to_string())?;
+async fn reset_timer(confirmation: Option<String>, engine: State<'_, EngineHandle>) -> Result<(), String> {
+    engine.reset(confirmation).await??;
     Ok(())
 }
 
 #[tauri::command]
-async fn skip_break(
-    engine: State<'_, SharedTimerEngine>,
-    app: AppHandle,
-) -> Result<(), String> {
-    {
-        let mut engine_guard = engine.lock().await;
-        engine_guard.skip_break();
-        engine_guard.emit_update(&app).map_err(|e| e.to_string())?;
+async fn extend_phase(minutes: Option<u32>, engine: State<'_, EngineHandle>) -> Result<(), String> {
+    engine.extend_phase(minutes).await??;
+    Ok(())
+}
+
+#[tauri::command]
+async fn reset_set(engine: State<'_, EngineHandle>) -> Result<(), String> {
+    engine.reset_set().await??;
+    Ok(())
+}
+
+/// A refused skip keeps its structured reason; a stopped engine is reported as text.
+#[derive(Debug, serde::Serialize)]
+#[serde(untagged)]
+enum SkipError {
+    Denied(SkipDenied),
+    Engine(String),
+}
+
+impl From<SkipDenied> for SkipError {
+    fn from(denied: SkipDenied) -> Self {
+        SkipError::Denied(denied)
+    }
+}
+
+impl From<EngineStopped> for SkipError {
+    fn from(stopped: EngineStopped) -> Self {
+        SkipError::Engine(stopped.to_string())
     }
-    hide_guard(&app).await.map_err(|e| e.to_string())?;
+}
+
+#[tauri::command]
+async fn skip_break(confirmation: Option<String>, engine: State<'_, EngineHandle>) -> Result<(), SkipError> {
+    engine.skip_break(confirmation).await??;
     Ok(())
 }
 
 #[tauri::command]
-async fn get_timer_state(engine: State<'_, SharedTimerEngine>) -> Result<TimerUpdate, String> {
-    let engine_guard = engine.lock().await;
-    Ok(engine_guard.get_update())
+async fn get_skip_status(engine: State<'_, EngineHandle>) -> Result<SkipStatus, String> {
+    Ok(engine.skip_status().await?)
+}
+
+#[tauri::command]
+async fn get_timer_state(engine: State<'_, EngineHandle>) -> Result<TimerUpdate, String> {
+    Ok(engine.get_update().await?)
+}
+
+#[tauri::command]
+async fn get_history(
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    history: State<'_, Arc<HistoryLog>>,
+) -> Result<Vec<SessionRecord>, String> {
+    history.query(from, to)
+}
+
+#[tauri::command]
+async fn get_daily_stats(
+    days: Option<u32>,
+    history: State<'_, Arc<HistoryLog>>,
+) -> Result<Vec<DailyStats>, String> {
+    service::daily_stats(&history, days)
+}
+
+#[tauri::command]
+async fn get_weekly_stats(
+    weeks: Option<u32>,
+    history: State<'_, Arc<HistoryLog>>,
+) -> Result<Vec<WeeklyStats>, String> {
+    service::weekly_stats(&history, weeks)
+}
+
+#[tauri::command]
+async fn get_stats_summary(history: State<'_, Arc<HistoryLog>>) -> Result<StatsSummary, String> {
+    service::stats_summary(&history)
+}
+
+#[tauri::command]
+async fn get_settings(settings_store: State<'_, SharedSettings>) -> Result<Settings, String> {
+    let store_guard = settings_store.lock().await;
+    Ok(store_guard.get().clone())
+}
+
+#[tauri::command]
+async fn update_settings(
+    settings: Settings,
+    settings_store: State<'_, SharedSettings>,
+    engine: State<'_, EngineHandle>,
+) -> Result<Settings, String> {
+    service::modify_settings(&settings_store, &engine, |current| {
+        *current = settings;
+        Ok(())
+    })
+    .await
+}
+
+#[tauri::command]
+async fn list_profiles(settings_store: State<'_, SharedSettings>) -> Result<Vec<Profile>, String> {
+    let store_guard = settings_store.lock().await;
+    Ok(store_guard.get().profiles.clone())
+}
+
+#[tauri::command]
+async fn create_profile(
+    profile: Profile,
+    settings_store: State<'_, SharedSettings>,
+    engine: State<'_, EngineHandle>,
+) -> Result<Settings, String> {
+    service::modify_settings(&settings_store, &engine, |settings| settings.create_profile(profile)).await
+}
+
+#[tauri::command]
+async fn update_profile(
+    name: String,
+    profile: Profile,
+    settings_store: State<'_, SharedSettings>,
+    engine: State<'_, EngineHandle>,
+) -> Result<Settings, String> {
+    service::modify_settings(&settings_store, &engine, |settings| settings.update_profile(&name, profile)).await
+}
+
+#[tauri::command]
+async fn delete_profile(
+    name: String,
+    settings_store: State<'_, SharedSettings>,
+    engine: State<'_, EngineHandle>,
+) -> Result<Settings, String> {
+    service::modify_settings(&settings_store, &engine, |settings| settings.delete_profile(&name)).await
+}
+
+#[tauri::command]
+async fn switch_profile(
+    name: String,
+    settings_store: State<'_, SharedSettings>,
+    engine: State<'_, EngineHandle>,
+) -> Result<Settings, String> {
+    service::modify_settings(&settings_store, &engine, |settings| settings.switch_profile(&name)).await
+}
+
+#[tauri::command]
+async fn test_hook(
+    change: PhaseChange,
+    settings_store: State<'_, SharedSettings>,
+    engine: State<'_, EngineHandle>,
+) -> Result<HookOutcome, String> {
+    // A dry run against the current state; the timer itself is left alone
+    let update = engine.get_update().await?;
+    hooks::dry_run(&settings_store, change, update).await
+}
+
+#[tauri::command]
+fn get_webhook_deliveries(
+    webhooks: State<'_, Arc<WebhookDispatcher>>,
+) -> Result<Vec<DeliveryLogEntry>, String> {
+    Ok(webhooks.deliveries())
+}
+
+#[tauri::command]
+fn list_shortcuts(registry: State<'_, ShortcutRegistry>) -> Result<Vec<ShortcutBinding>, String> {
+    Ok(registry.bindings())
 }
 
 fn main() {
@@ -82,8 +279,45 @@ fn main() {
             #[cfg(target_os = "macos")]
             app.set_activation_policy(ActivationPolicy::Accessory);
 
-            // Initialize timer engine
-            let engine: SharedTimerEngine = Arc::new(Mutex::new(TimerEngine::new()));
+            // Load persisted settings from the app config dir
+            let settings_path = app.path().app_config_dir()?.join(SETTINGS_FILE_NAME);
+            let settings_store = SettingsStore::load(settings_path);
+
+            // Initialize timer engine, snapshotting it and its history to the app data dir
+            let data_dir = app.path().app_data_dir()?;
+            let snapshot_store = FileSnapshotStore::new(data_dir.join(SESSION_FILE_NAME));
+            let history = Arc::new(HistoryLog::new(data_dir.join(HISTORY_FILE_NAME)));
+            let snapshot = snapshot_store.load();
+            // Engine events go to the webviews and to every in-process subscriber
+            let engine_events = Arc::new(BroadcastSink::new(ENGINE_EVENT_BUFFER));
+            let sinks: Vec<Arc<dyn EventSink>> = vec![
+                Arc::new(TauriEventSink::new(app.handle().clone())),
+                engine_events.clone(),
+            ];
+            let mut timer_engine = TimerEngine::new(
+                settings_store.get().active(),
+                Arc::new(SystemClock),
+                Arc::new(FanOutSink::new(sinks)),
+            );
+            timer_engine.set_snapshot_store(Arc::new(snapshot_store));
+            timer_engine.set_history_recorder(history.clone());
+            timer_engine.set_break_warnings(&settings_store.get().break_warning_seconds);
+            timer_engine.set_break_lockdown(kiosk_mode_enabled());
+            if test_mode_enabled() {
+                let lengths = TEST_PHASE_LENGTHS;
+                eprintln!(
+                    "[Timer] {} enabled: focus={}s, short_break={}s, long_break={}s",
+                    TEST_MODE_ENV,
+                    lengths.focus.as_secs(),
+                    lengths.short_break.as_secs(),
+                    lengths.long_break.as_secs()
+                );
+                timer_engine.override_phase_lengths(lengths);
+            }
+            let phase_updates = timer_engine.subscribe_phase();
+            let http_api_settings = settings_store.get().http_api;
+            let settings_changes = settings_store.subscribe();
+            let settings_store: SharedSettings = Arc::new(Mutex::new(settings_store));
 
             // Start as a menubar-style app: keep main window hidden initially.
             if let Some(main_window) = app.get_webview_window("main") {
@@ -94,67 +328,110 @@ fn main() {
                 let _ = guard_window.hide();
             }
 
-            let app_handle_for_tray = app.handle().clone();
-            let mut tray_builder = TrayIconBuilder::new()
-                .tooltip("TimeToThink")
-                .on_tray_icon_event(move |_tray, event| {
-                    if let TrayIconEvent::Click {
-                        button: MouseButton::Left,
-                        button_state: MouseButtonState::Up,
-                        ..
-                    } = event
-                    {
-                        toggle_main_window(&app_handle_for_tray);
-                    }
-                });
+            // The tray menu and countdown follow the engine once it is running
+            let tray_menu = tray::build(app)?;
+            app.manage(tray_menu.clone());
 
-            if let Some(icon) = app.default_window_icon() {
-                tray_builder = tray_builder.icon(icon.clone());
-            } else {
-                tray_builder = tray_builder.icon(Image::new(&FALLBACK_TRAY_ICON, 1, 1));
-            }
+            // Store settings in app state
+            app.manage(settings_store.clone());
+            app.manage(history.clone());
 
-            tray_builder.build(app)?;
+            // Run the user's hook commands as the timer changes phase
+            tauri::async_runtime::spawn(hooks::run_hooks(settings_store.clone(), engine_events.subscribe()));
 
-            // Store engine in app state
-            app.manage(engine.clone());
+            // Post phase transitions to the configured webhooks, resuming any
+            // deliveries left over from the last run
+            let webhooks = Arc::new(WebhookDispatcher::load(
+                data_dir.join(WEBHOOK_QUEUE_FILE_NAME),
+                DEFAULT_QUEUE_CAPACITY,
+            ));
+            tauri::async_runtime::spawn(webhooks.clone().run(settings_store.clone(), engine_events.subscribe()));
+            app.manage(webhooks);
 
-            // Listen for show_guard event from timer engine
-            let app_handle_for_guard = app.handle().clone();
-            app.listen("show_guard", move |_| {
-                let handle = app_handle_for_guard.clone();
-                tauri::async_runtime::spawn(async move {
-                    let _ = show_guard(&handle).await;
-                });
+            // Give people a heads-up before the guard locks the screen
+            let app_handle_for_warning = app.handle().clone();
+            app.listen(EventName::BreakWarning.as_str(), move |event| {
+                match serde_json::from_str::<BreakWarning>(event.payload()) {
+                    Ok(warning) => announce_break_warning(&app_handle_for_warning, &warning),
+                    Err(e) => eprintln!("Invalid break_warning payload: {}", e),
+                }
             });
 
-            // Listen for hide_guard event from timer engine
-            let app_handle_for_hide = app.handle().clone();
-            app.listen("hide_guard", move |_| {
-                let handle = app_handle_for_hide.clone();
+            // Bring the main window forward so the extension prompt is seen before the break
+            let app_handle_for_offer = app.handle().clone();
+            app.listen(EventName::ExtensionOffer.as_str(), move |_| {
+                show_main_window(&app_handle_for_offer);
+            });
+
+            // Resume the previous session once the listeners are in place
+            if let Some(snapshot) = snapshot {
+                if let Err(e) = timer_engine.restore(&snapshot) {
+                    eprintln!("Failed to restore session: {}", e);
+                }
+            }
+
+            // Hand the engine to its own task; commands reach it through the handle
+            let (engine, engine_actor) = engine_actor::channel(timer_engine);
+            tauri::async_runtime::spawn(engine_actor.run());
+
+            // Global shortcuts drive the engine too, so register them once it is running
+            app.manage(ShortcutRegistry::default());
+            tauri::async_runtime::spawn(shortcuts::run_shortcut_sync(
+                app.handle().clone(),
+                settings_changes.clone(),
+            ));
+            tauri::async_runtime::spawn(tray::run_tray_updater(
+                app.handle().clone(),
+                tray_menu,
+                engine.clone(),
+                settings_changes,
+                engine_events.subscribe(),
+            ));
+
+            // Let `timetothink-cli` drive the same engine over a local socket
+            #[cfg(unix)]
+            {
+                let engine = engine.clone();
                 tauri::async_runtime::spawn(async move {
-                    let _ = hide_guard(&handle).await;
+                    let socket_path = ipc::default_socket_path();
+                    if let Err(e) = ipc::serve(&socket_path, engine).await {
+                        eprintln!("Command-line control unavailable: {}", e);
+                    }
                 });
-            });
+            }
 
-            // Start timer loop
-            let app_handle_for_timer = app.handle().clone();
-            tauri::async_runtime::spawn(start_timer_loop(app_handle_for_timer, engine.clone()));
+            // Serve the local HTTP API when it has been switched on
+            if http_api_settings.enabled {
+                let token_path = app.path().app_config_dir()?.join(http_api::TOKEN_FILE_NAME);
+                match http_api::load_or_create_token(&token_path) {
+                    Ok(token) => {
+                        let state = ApiState {
+                            engine: engine.clone(),
+                            settings: settings_store,
+                            history,
+                            events: engine_events,
+                            token: token.into(),
+                        };
+                        tauri::async_runtime::spawn(async move {
+                            if let Err(e) = http_api::serve(http_api_settings.port, state).await {
+                                eprintln!("HTTP API unavailable: {}", e);
+                            }
+                        });
+                    }
+                    Err(e) => eprintln!("HTTP API unavailable: {}", e),
+                }
+            }
+            app.manage(engine);
 
-            // Start guard polling
-            let app_handle_for_polling = app.handle().clone();
-            tauri::async_runtime::spawn(start_guard_polling(app_handle_for_polling, engine.clone()));
+            // Show and hide the guard as the engine changes phase
+            tauri::async_runtime::spawn(run_guard_controller(app.handle().clone(), phase_updates));
 
             Ok(())
         })
         .plugin(tauri_plugin_shell::init())
-        .invoke_handler(tauri::generate_handler![
-            start_timer,
-            pause_timer,
-            reset_timer,
-            skip_break,
-            get_timer_state
-        ])
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        .invoke_handler(timetothink_core::app_commands!(tauri::generate_handler))
         .build(tauri::generate_context!())
         .expect("error while building tauri application");
 
@@ -164,13 +441,9 @@ fn main() {
         }
 
         if let RunEvent::ExitRequested { api, .. } = event {
-            if let Some(engine) = app_handle.try_state::<SharedTimerEngine>() {
-                let should_block_exit = match engine.try_lock() {
-                    Ok(engine_guard) => engine_guard.get_state().is_break() && engine_guard.is_running(),
-                    Err(_) => true,
-                };
-
-                if should_block_exit {
+            if let Some(engine) = app_handle.try_state::<EngineHandle>() {
+                let phase = engine.phase();
+                if phase.break_locked && phase.is_running {
                     eprintln!("Exit blocked: kiosk mode is active during break");
                     api.prevent_exit();
                 }
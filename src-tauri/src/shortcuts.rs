@@ -0,0 +1,102 @@
+use crate::show_main_window;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutEvent, ShortcutState};
+use timetothink_core::engine_actor::EngineHandle;
+use timetothink_core::settings::{Settings, ShortcutSettings};
+use timetothink_core::shortcuts::{ShortcutAction, ShortcutBinding};
+use tokio::sync::watch;
+
+/// The shortcuts as they were last handed to the OS.
+#[derive(Default)]
+pub struct ShortcutRegistry {
+    bindings: Mutex<Vec<ShortcutBinding>>,
+}
+
+impl ShortcutRegistry {
+    pub fn bindings(&self) -> Vec<ShortcutBinding> {
+        self.bindings.lock().map(|bindings| bindings.clone()).unwrap_or_default()
+    }
+}
+
+/// Replaces every registered shortcut with those in `shortcuts`. A combination
+/// the OS refuses, usually because another app holds it, is reported in its
+/// binding rather than failing the others.
+fn register(app: &AppHandle, shortcuts: &ShortcutSettings) -> Vec<ShortcutBinding> {
+    let global_shortcut = app.global_shortcut();
+    if let Err(e) = global_shortcut.unregister_all() {
+        eprintln!("[Shortcuts] Failed to clear shortcuts: {}", e);
+    }
+    ShortcutAction::ALL
+        .into_iter()
+        .map(|action| {
+            let accelerator = shortcuts.accelerator(action).map(str::to_string);
+            let (registered, error) = match &accelerator {
+                None => (false, None),
+                Some(accelerator) => {
+                    let handler = move |app: &AppHandle, _: &Shortcut, event: ShortcutEvent| {
+                        if event.state == ShortcutState::Pressed {
+                            let app = app.clone();
+                            tauri::async_runtime::spawn(async move {
+                                if let Err(e) = run_action(&app, action).await {
+                                    eprintln!("[Shortcuts] {:?}: {}", action, e);
+                                }
+                            });
+                        }
+                    };
+                    match global_shortcut.on_shortcut(accelerator.as_str(), handler) {
+                        Ok(()) => (true, None),
+                        Err(e) => {
+                            eprintln!("[Shortcuts] Could not register {} for {:?}: {}", accelerator, action, e);
+                            (false, Some(e.to_string()))
+                        }
+                    }
+                }
+            };
+            ShortcutBinding {
+                action,
+                accelerator,
+                registered,
+                error,
+            }
+        })
+        .collect()
+}
+
+/// The same engine operations the `start_timer`, `pause_timer` and
+/// `extend_phase` commands use.
+async fn run_action(app: &AppHandle, action: ShortcutAction) -> Result<(), String> {
+    let engine = app.state::<EngineHandle>();
+    match action {
+        ShortcutAction::ToggleTimer => {
+            if engine.get_update().await?.is_running {
+                engine.pause().await??;
+            } else {
+                engine.start().await??;
+            }
+        }
+        ShortcutAction::ShowWindow => show_main_window(app),
+        ShortcutAction::ExtendFocus => engine.extend_phase(None).await??,
+    }
+    Ok(())
+}
+
+/// Registers the configured shortcuts and registers them again whenever saved
+/// settings change them. Returns once the settings store is dropped.
+pub async fn run_shortcut_sync(app: AppHandle, mut settings: watch::Receiver<Settings>) {
+    let mut registered: Option<ShortcutSettings> = None;
+    loop {
+        let shortcuts = settings.borrow_and_update().shortcuts.clone();
+        if registered.as_ref() != Some(&shortcuts) {
+            let bindings = register(&app, &shortcuts);
+            if let Ok(mut current) = app.state::<ShortcutRegistry>().bindings.lock() {
+                *current = bindings;
+            }
+            registered = Some(shortcuts);
+        }
+
+        if settings.changed().await.is_err() {
+            break;
+        }
+    }
+}
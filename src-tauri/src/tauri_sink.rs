@@ -0,0 +1,27 @@
+use tauri::{AppHandle, Emitter};
+use timetothink_core::events::{EngineEvent, EventSink};
+
+/// Forwards engine events to the webviews as Tauri events.
+pub struct TauriEventSink {
+    app: AppHandle,
+}
+
+impl TauriEventSink {
+    pub fn new(app: AppHandle) -> Self {
+        Self { app }
+    }
+}
+
+impl EventSink for TauriEventSink {
+    fn emit(&self, event: EngineEvent) -> Result<(), Box<dyn std::error::Error>> {
+        let name = event.name();
+        match event {
+            EngineEvent::TimerUpdate(update) => self.app.emit(name, update)?,
+            EngineEvent::ExtensionOffer(offer) => self.app.emit(name, offer)?,
+            EngineEvent::BreakWarning(warning) => self.app.emit(name, warning)?,
+            EngineEvent::PhaseTransition(transition) => self.app.emit(name, transition)?,
+            EngineEvent::ShowGuard | EngineEvent::HideGuard => self.app.emit(name, ())?,
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,271 @@
+use crate::{show_main_window, toggle_main_window};
+use tauri::image::Image;
+use tauri::menu::{CheckMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{App, AppHandle, Manager};
+use timetothink_core::engine_actor::EngineHandle;
+use timetothink_core::events::EngineEvent;
+use timetothink_core::service;
+use timetothink_core::settings::{Settings, SharedSettings};
+use timetothink_core::skip_policy::SkipDenied;
+use timetothink_core::tray_icon::{self, TrayIconState, TRAY_ICON_SIZE};
+use timetothink_core::tray_status::TrayStatus;
+use timetothink_core::types::{AllowedControls, TimerState};
+use tokio::sync::{broadcast, watch};
+
+const TRAY_ID: &str = "main";
+pub const TRAY_TOOLTIP: &str = "TimeToThink";
+
+const STATUS_ID: &str = "status";
+const START_ID: &str = "start";
+const PAUSE_ID: &str = "pause";
+const RESET_ID: &str = "reset";
+const SKIP_ID: &str = "skip";
+const EXTEND_ID: &str = "extend";
+const SHOW_ID: &str = "show";
+const QUIT_ID: &str = "quit";
+/// Profile items are identified by this prefix followed by the profile name.
+const PROFILE_ID_PREFIX: &str = "profile:";
+
+/// The menu items that change while the app runs.
+#[derive(Clone)]
+pub struct TrayMenu {
+    status: MenuItem,
+    start: MenuItem,
+    pause: MenuItem,
+    reset: MenuItem,
+    skip: MenuItem,
+    extend: MenuItem,
+    profiles: Submenu,
+}
+
+/// What the profile submenu and extend item were last built from.
+#[derive(PartialEq)]
+struct ProfileMenuState {
+    names: Vec<String>,
+    active: String,
+    extension_minutes: u32,
+}
+
+impl ProfileMenuState {
+    fn of(settings: &Settings) -> Self {
+        Self {
+            names: settings.profiles.iter().map(|profile| profile.name.clone()).collect(),
+            active: settings.active_profile.clone(),
+            extension_minutes: settings.active().extension.minutes,
+        }
+    }
+}
+
+fn icon(state: &TrayIconState) -> Image<'static> {
+    Image::new_owned(tray_icon::render(state, TRAY_ICON_SIZE), TRAY_ICON_SIZE, TRAY_ICON_SIZE)
+}
+
+/// Builds the tray icon: left click toggles the main window, right click opens the menu.
+pub fn build(app: &App) -> tauri::Result<TrayMenu> {
+    let menu = TrayMenu {
+        status: MenuItem::with_id(app, STATUS_ID, TRAY_TOOLTIP, false, None::<&str>)?,
+        start: MenuItem::with_id(app, START_ID, "Start", false, None::<&str>)?,
+        pause: MenuItem::with_id(app, PAUSE_ID, "Pause", false, None::<&str>)?,
+        reset: MenuItem::with_id(app, RESET_ID, "Reset", false, None::<&str>)?,
+        skip: MenuItem::with_id(app, SKIP_ID, "Skip Break", false, None::<&str>)?,
+        extend: MenuItem::with_id(app, EXTEND_ID, "Extend", false, None::<&str>)?,
+        profiles: Submenu::new(app, "Profile", true)?,
+    };
+    let tray_menu = Menu::with_items(
+        app,
+        &[
+            &menu.status,
+            &PredefinedMenuItem::separator(app)?,
+            &menu.start,
+            &menu.pause,
+            &menu.reset,
+            &menu.skip,
+            &menu.extend,
+            &PredefinedMenuItem::separator(app)?,
+            &menu.profiles,
+            &PredefinedMenuItem::separator(app)?,
+            &MenuItem::with_id(app, SHOW_ID, "Show TimeToThink", true, None::<&str>)?,
+            &MenuItem::with_id(app, QUIT_ID, "Quit", true, None::<&str>)?,
+        ],
+    )?;
+
+    // Drawn as an idle focus phase until the engine reports its state
+    let idle = TrayIconState::new(TimerState::Focus, false, 0.0);
+    TrayIconBuilder::with_id(TRAY_ID)
+        .icon(icon(&idle))
+        .tooltip(TRAY_TOOLTIP)
+        .menu(&tray_menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(handle_menu_event)
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                toggle_main_window(tray.app_handle());
+            }
+        })
+        .build(app)?;
+    Ok(menu)
+}
+
+impl TrayMenu {
+    fn show_status(&self, app: &AppHandle, status: &str) {
+        let _ = self.status.set_text(status);
+        if let Some(tray) = app.tray_by_id(TRAY_ID) {
+            let _ = tray.set_tooltip(Some(format!("{} — {}", TRAY_TOOLTIP, status)));
+            let _ = tray.set_title(Some(status));
+        }
+    }
+
+    fn show_icon(&self, app: &AppHandle, state: &TrayIconState) {
+        if let Some(tray) = app.tray_by_id(TRAY_ID) {
+            if let Err(e) = tray.set_icon(Some(icon(state))) {
+                eprintln!("[Tray] Failed to update the icon: {}", e);
+            }
+        }
+    }
+
+    fn show_controls(&self, controls: &AllowedControls) {
+        let _ = self.start.set_enabled(controls.start);
+        let _ = self.pause.set_enabled(controls.pause);
+        let _ = self.reset.set_enabled(controls.reset);
+        let _ = self.skip.set_enabled(controls.skip);
+        let _ = self.extend.set_enabled(controls.extend);
+    }
+
+    fn show_profiles(&self, app: &AppHandle, state: &ProfileMenuState) -> tauri::Result<()> {
+        self.extend.set_text(format!("Extend by {} min", state.extension_minutes))?;
+        while self.profiles.remove_at(0)?.is_some() {}
+        for name in &state.names {
+            let id = format!("{}{}", PROFILE_ID_PREFIX, name);
+            let item = CheckMenuItem::with_id(app, id, name, true, *name == state.active, None::<&str>)?;
+            self.profiles.append(&item)?;
+        }
+        Ok(())
+    }
+
+    /// Puts the check mark back on `active`; the platform moves it on every click,
+    /// even when the switch is refused.
+    fn check_profile(&self, active: &str) -> tauri::Result<()> {
+        for item in self.profiles.items()? {
+            if let Some(item) = item.as_check_menuitem() {
+                item.set_checked(item.text()? == active)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Keeps the tray's icon, countdown, break warning and enabled items in step with
+/// the engine, and its profile list in step with the settings, until either goes away.
+/// Menu items are only touched when what they show has changed.
+pub async fn run_tray_updater(
+    app: AppHandle,
+    menu: TrayMenu,
+    engine: EngineHandle,
+    mut settings: watch::Receiver<Settings>,
+    mut events: broadcast::Receiver<EngineEvent>,
+) {
+    let mut update = match engine.get_update().await {
+        Ok(update) => update,
+        Err(e) => {
+            eprintln!("[Tray] {}", e);
+            return;
+        }
+    };
+    let mut status = TrayStatus::default();
+    let mut shown_status = None;
+    let mut shown_icon = None;
+    let mut shown_controls = None;
+    let mut shown_profiles = None;
+    let mut settings_changed = true;
+    loop {
+        let text = status.text(&update);
+        if shown_status.as_ref() != Some(&text) {
+            menu.show_status(&app, &text);
+            shown_status = Some(text);
+        }
+        // Progress is quantized, so the icon is redrawn at most PROGRESS_STEPS times a phase
+        let icon_state = TrayIconState::of(&update);
+        if shown_icon != Some(icon_state) {
+            menu.show_icon(&app, &icon_state);
+            shown_icon = Some(icon_state);
+        }
+        if shown_controls != Some(update.controls) {
+            menu.show_controls(&update.controls);
+            shown_controls = Some(update.controls);
+        }
+        if settings_changed {
+            let profiles = ProfileMenuState::of(&settings.borrow_and_update());
+            if shown_profiles.as_ref() != Some(&profiles) {
+                if let Err(e) = menu.show_profiles(&app, &profiles) {
+                    eprintln!("[Tray] Failed to update the profile menu: {}", e);
+                }
+                shown_profiles = Some(profiles);
+            }
+            settings_changed = false;
+        }
+
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(EngineEvent::TimerUpdate(next)) => update = next,
+                Ok(EngineEvent::BreakWarning(warning)) => status.warn(warning),
+                Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => return,
+            },
+            changed = settings.changed() => {
+                if changed.is_err() {
+                    return;
+                }
+                settings_changed = true;
+            }
+        }
+    }
+}
+
+fn handle_menu_event(app: &AppHandle, event: MenuEvent) {
+    let id = event.id().as_ref().to_string();
+    match id.as_str() {
+        SHOW_ID => show_main_window(app),
+        QUIT_ID => app.exit(0),
+        _ => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = run_menu_action(&app, &id).await {
+                    eprintln!("[Tray] {}: {}", id, e);
+                }
+            });
+        }
+    }
+}
+
+async fn run_menu_action(app: &AppHandle, id: &str) -> Result<(), String> {
+    let engine = app.state::<EngineHandle>();
+    match id {
+        START_ID => engine.start().await??,
+        PAUSE_ID => engine.pause().await??,
+        RESET_ID => engine.reset(None).await??,
+        EXTEND_ID => engine.extend_phase(None).await??,
+        SKIP_ID => match engine.skip_break(None).await? {
+            Ok(()) => {}
+            // The tray cannot ask for the phrase, so hand over to the main window
+            Err(SkipDenied::ConfirmationRequired { .. }) => show_main_window(app),
+            Err(denied) => return Err(denied.to_string()),
+        },
+        _ => {
+            if let Some(name) = id.strip_prefix(PROFILE_ID_PREFIX) {
+                let settings = app.state::<SharedSettings>();
+                let switched =
+                    service::modify_settings(&settings, &engine, |settings| settings.switch_profile(name)).await;
+                let active = settings.lock().await.get().active_profile.clone();
+                app.state::<TrayMenu>().check_profile(&active).map_err(|e| e.to_string())?;
+                switched?;
+            }
+        }
+    }
+    Ok(())
+}
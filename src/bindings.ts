@@ -0,0 +1,295 @@
+// Generated by src-tauri/core/src/bindings.rs. Do not edit by hand; run
+// `npm run bindings` to regenerate.
+import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+
+/**
+ * Which timer controls the engine would currently accept.
+ */
+export type AllowedControls = { start: boolean, pause: boolean, 
+/**
+ * During an enforced break a reset only goes through as a skip.
+ */
+reset: boolean, 
+/**
+ * Confirmation phrases are not considered; see `get_skip_status`.
+ */
+skip: boolean, extend: boolean, };
+
+/**
+ * Sent as a focus phase crosses each configured warning threshold.
+ */
+export type BreakWarning = { seconds_left: number, next_state: TimerState, };
+
+export type DailyStats = { date: string, focus_minutes: number, completed_pomodoros: number, breaks_taken: number, breaks_skipped: number, };
+
+/**
+ * A delivery attempt, as shown by the delivery log.
+ */
+export type DeliveryLogEntry = { id: string, url: string, event: PhaseChange, attempt: number, at: string, outcome: DeliveryOutcome, status_code: number | null, error: string | null, };
+
+export type DeliveryOutcome = "delivered" | "retrying" | "failed" | "dropped";
+
+/**
+ * How firmly breaks are enforced.
+ */
+export type Enforcement = "strict" | "standard" | "gentle";
+
+/**
+ * Sent once when a focus phase is about to end and can still be extended.
+ */
+export type ExtensionOffer = { remaining_seconds: number, extension_minutes: number, extensions_left: number, };
+
+/**
+ * Limits on extending a focus phase; counted per focus/break cycle.
+ */
+export type ExtensionPolicy = { 
+/**
+ * Length of a single extension.
+ */
+minutes: number, 
+/**
+ * Zero disables extensions.
+ */
+max_per_cycle: number, max_total_minutes: number, };
+
+/**
+ * What happened when a hook ran.
+ */
+export type HookOutcome = { change: PhaseChange, 
+/**
+ * `None` when the hook was killed by a signal or timed out.
+ */
+exit_code: number | null, timed_out: boolean, stdout: string, stderr: string, 
+/**
+ * Whether `stdout` or `stderr` was cut at `MAX_CAPTURED_OUTPUT` bytes.
+ */
+output_truncated: boolean, duration_ms: number, };
+
+/**
+ * Commands run when the timer changes phase, each written as the program
+ * followed by its arguments; an empty list runs nothing. Commands are not
+ * passed through a shell, so use `["sh", "-c", "..."]` for pipes and the like.
+ */
+export type HookSettings = { focus_started: Array<string>, focus_ended: Array<string>, break_started: Array<string>, break_ended: Array<string>, skipped: Array<string>, reset: Array<string>, 
+/**
+ * A hook still running after this long is killed.
+ */
+timeout_seconds: number, };
+
+/**
+ * The local HTTP API for scripts and dashboards. Off by default; changes
+ * take effect the next time the app starts.
+ */
+export type HttpApiSettings = { enabled: boolean, 
+/**
+ * Port on 127.0.0.1.
+ */
+port: number, };
+
+/**
+ * Decides when a long break replaces a short one.
+ */
+export type LongBreakCadence = { "mode": "every_nth_pomodoro", interval: number, } | { "mode": "focus_minutes", minutes: number, };
+
+/**
+ * How the timer came to be in its current phase.
+ */
+export type PhaseChange = "focus_started" | "focus_ended" | "break_started" | "break_ended" | "skipped" | "reset";
+
+/**
+ * Extra time granted to a running focus phase.
+ */
+export type PhaseExtension = { at: string, seconds: number, };
+
+/**
+ * Sent for every [`PhaseChange`], after the state it led to is in place.
+ */
+export type PhaseTransition = { change: PhaseChange, from: TimerState, to: TimerState, at: string, update: TimerUpdate, };
+
+/**
+ * A named rhythm: phase durations, long-break cadence and enforcement.
+ */
+export type Profile = { name: string, focus_minutes: number, short_break_minutes: number, long_break_minutes: number, long_break_cadence: LongBreakCadence, enforcement: Enforcement, extension: ExtensionPolicy, skip: SkipPolicy, };
+
+export type SessionOutcome = "completed" | "skipped" | "reset";
+
+/**
+ * One finished or aborted phase.
+ */
+export type SessionRecord = { phase: TimerState, outcome: SessionOutcome, 
+/**
+ * Length the phase was scheduled with, before any extensions.
+ */
+planned_seconds: number, 
+/**
+ * Time the phase actually spent running, excluding pauses.
+ */
+actual_seconds: number, started_at: string, ended_at: string, extensions?: Array<PhaseExtension>, };
+
+/**
+ * Where the timer stands within the current long-break set. Only the
+ * `*_per_set` field matching the profile's cadence is present.
+ */
+export type SetPosition = { 
+/**
+ * Pomodoros completed since the last long break.
+ */
+pomodoros: number, pomodoros_per_set: number | null, focus_minutes: number, focus_minutes_per_set: number | null, };
+
+export type Settings = { active_profile: string, profiles: Array<Profile>, 
+/**
+ * Seconds before a break at which a warning is shown.
+ */
+break_warning_seconds: Array<number>, http_api: HttpApiSettings, hooks: HookSettings, webhooks: Array<WebhookTarget>, shortcuts: ShortcutSettings, };
+
+export type ShortcutAction = "toggle_timer" | "show_window" | "extend_focus";
+
+/**
+ * A shortcut as the settings describe it and as the OS took it.
+ */
+export type ShortcutBinding = { action: ShortcutAction, 
+/**
+ * `None` when the action has no shortcut.
+ */
+accelerator: string | null, registered: boolean, 
+/**
+ * Why registration failed, typically because another app holds the keys.
+ */
+error: string | null, };
+
+/**
+ * Global keyboard shortcuts, written as accelerators such as
+ * `CmdOrCtrl+Alt+P`; `null` leaves an action without one.
+ */
+export type ShortcutSettings = { toggle_timer: string | null, show_window: string | null, extend_focus: string | null, };
+
+/**
+ * Why a skip was refused. Serialized with a `reason` tag so front ends can react to each case.
+ */
+export type SkipDenied = { "reason": "not_in_break" } | { "reason": "disabled_by_profile" } | { "reason": "too_early", unlocks_in_seconds: number, } | { "reason": "quota_exhausted", used: number, quota: number, } | { "reason": "confirmation_required", phrase: string, };
+
+/**
+ * Conditions a break must meet before it can be skipped.
+ */
+export type SkipPolicy = { 
+/**
+ * Skips allowed per local calendar day; `None` means unlimited.
+ */
+daily_quota: number | null, 
+/**
+ * Percentage of the break that must have elapsed before skipping unlocks.
+ */
+min_elapsed_percent: number, 
+/**
+ * When set, this phrase has to be typed to confirm a skip.
+ */
+confirmation_phrase: string | null, };
+
+/**
+ * What the front end needs to present the skip control.
+ */
+export type SkipStatus = { 
+/**
+ * Why the skip button should be disabled right now, if it should.
+ */
+denied: SkipDenied | null, 
+/**
+ * `None` when the quota is unlimited.
+ */
+skips_left_today: number | null, confirmation_phrase: string | null, };
+
+export type StatsSummary = { today: DailyStats, this_week: WeeklyStats, 
+/**
+ * Percentage of breaks over the last week that ran to completion;
+ * `None` when there were no breaks.
+ */
+break_compliance_percent: number | null, streaks: Streaks, };
+
+export type Streaks = { current_days: number, longest_days: number, };
+
+export type TimerState = "Focus" | "ShortBreak" | "LongBreak";
+
+export type TimerUpdate = { state: TimerState, remaining_seconds: number, 
+/**
+ * Length of the current phase, including any extensions.
+ */
+total_seconds: number, 
+/**
+ * Fraction of the phase already elapsed, from 0.0 to 1.0.
+ */
+progress: number, 
+/**
+ * When the phase will end; only known while running.
+ */
+ends_at: string | null, 
+/**
+ * The phase that follows this one if it runs to completion.
+ */
+next_state: TimerState, set_position: SetPosition, completed_pomodoros: number, is_running: boolean, controls: AllowedControls, };
+
+/**
+ * An endpoint told about phase transitions by a signed POST.
+ */
+export type WebhookTarget = { 
+/**
+ * A plain `http://` URL; TLS is not built in.
+ */
+url: string, 
+/**
+ * Key for the `X-TimeToThink-Signature` HMAC.
+ */
+secret: string, 
+/**
+ * Transitions to send; all of them when empty.
+ */
+events: Array<PhaseChange>, };
+
+export type WeeklyStats = { 
+/**
+ * Monday of the week.
+ */
+week_start: string, focus_minutes: number, completed_pomodoros: number, };
+
+export const commands = {
+  startTimer: () => invoke<null>('start_timer'),
+  pauseTimer: () => invoke<null>('pause_timer'),
+  resetTimer: (args: { confirmation?: string | null } = {}) => invoke<null>('reset_timer', args),
+  extendPhase: (args: { minutes?: number | null } = {}) => invoke<null>('extend_phase', args),
+  resetSet: () => invoke<null>('reset_set'),
+  skipBreak: (args: { confirmation?: string | null } = {}) => invoke<null>('skip_break', args),
+  getSkipStatus: () => invoke<SkipStatus>('get_skip_status'),
+  getTimerState: () => invoke<TimerUpdate>('get_timer_state'),
+  getHistory: (args: { from?: string | null; to?: string | null } = {}) => invoke<Array<SessionRecord>>('get_history', args),
+  getDailyStats: (args: { days?: number | null } = {}) => invoke<Array<DailyStats>>('get_daily_stats', args),
+  getWeeklyStats: (args: { weeks?: number | null } = {}) => invoke<Array<WeeklyStats>>('get_weekly_stats', args),
+  getStatsSummary: () => invoke<StatsSummary>('get_stats_summary'),
+  getSettings: () => invoke<Settings>('get_settings'),
+  updateSettings: (args: { settings: Settings }) => invoke<Settings>('update_settings', args),
+  listProfiles: () => invoke<Array<Profile>>('list_profiles'),
+  createProfile: (args: { profile: Profile }) => invoke<Settings>('create_profile', args),
+  updateProfile: (args: { name: string; profile: Profile }) => invoke<Settings>('update_profile', args),
+  deleteProfile: (args: { name: string }) => invoke<Settings>('delete_profile', args),
+  switchProfile: (args: { name: string }) => invoke<Settings>('switch_profile', args),
+  testHook: (args: { change: PhaseChange }) => invoke<HookOutcome>('test_hook', args),
+  getWebhookDeliveries: () => invoke<Array<DeliveryLogEntry>>('get_webhook_deliveries'),
+  listShortcuts: () => invoke<Array<ShortcutBinding>>('list_shortcuts'),
+};
+
+export interface EventPayloads {
+  timer_update: TimerUpdate;
+  show_guard: null;
+  hide_guard: null;
+  extension_offer: ExtensionOffer;
+  break_warning: BreakWarning;
+  phase_transition: PhaseTransition;
+}
+
+export type EventName = keyof EventPayloads;
+
+export function onEvent<E extends EventName>(
+  event: E,
+  handler: (payload: EventPayloads[E]) => void,
+): Promise<UnlistenFn> {
+  return listen<EventPayloads[E]>(event, (e) => handler(e.payload));
+}